@@ -0,0 +1,60 @@
+use clap::{Parser, Subcommand};
+use log::LevelFilter;
+
+use crate::config::{
+    config_format::ConfigFormat, config_override::ConfigOverride,
+    config_reader_builder::DEFAULT_CONFIG_PATH,
+};
+
+/// Command-line arguments of the definitions provider.
+#[derive(Parser, Debug)]
+#[command(version, about)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Path of the configuration file.
+    #[arg(long, global = true, default_value = DEFAULT_CONFIG_PATH)]
+    pub config: String,
+
+    /// Format of the configuration file. Detected from the file's extension if omitted.
+    #[arg(long, global = true, value_enum)]
+    pub config_format: Option<ConfigFormat>,
+
+    /// Profile of the configuration file to merge over its base configuration.
+    #[arg(long, global = true, env = "COOPLAN_PROFILE")]
+    pub profile: Option<String>,
+
+    /// Overrides a configuration value, e.g. `--set output.amqp_channel_name=my-channel`.
+    /// Can be repeated; later overrides win.
+    #[arg(long = "set", global = true, value_name = "KEY=VALUE")]
+    pub overrides: Vec<ConfigOverride>,
+
+    /// Maximum log level. Takes precedence over `RUST_LOG`.
+    #[arg(long, global = true)]
+    pub log_level: Option<LevelFilter>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Reads and validates the configuration, reporting every problem found, then exits.
+    ValidateConfig,
+    /// Inspects the configuration.
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommand {
+    /// Prints the effective configuration, after applying the environment and the overrides,
+    /// with its credentials redacted.
+    Show {
+        /// Output format. Defaults to the format of the configuration file.
+        #[arg(long, value_enum)]
+        format: Option<ConfigFormat>,
+    },
+    /// Prints the JSON Schema of the configuration.
+    Schema,
+}
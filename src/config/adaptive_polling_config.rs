@@ -0,0 +1,19 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Polls faster right after the definitions changed, slowing down while they stay the same.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, JsonSchema)]
+pub struct AdaptivePollingConfig {
+    /// Seconds between two updates right after a change.
+    #[schemars(range(min = 1))]
+    pub min_interval_seconds: u64,
+    /// Factor the interval grows by after every update without changes, up to
+    /// `update_interval_seconds`.
+    #[serde(default = "default_multiplier")]
+    #[schemars(range(min = 1))]
+    pub multiplier: f64,
+}
+
+fn default_multiplier() -> f64 {
+    2.0
+}
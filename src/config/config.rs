@@ -1,19 +1,42 @@
-use cooplan_definition_git_downloader::git_config::GitConfig;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::secret;
+
 use super::{
-    definition_downloader_config::DefinitionDownloaderConfig, output_config::OutputConfig,
+    definition_downloader_config::DefinitionDownloaderConfig,
+    degraded_mode_config::DegradedModeConfig, git_config::GitSources, output_config::OutputConfig,
+    source_config::SourceConfig, status_config::StatusConfig,
 };
 
-#[derive(Deserialize, Serialize)]
+/// Configuration of the definitions provider.
+#[derive(Deserialize, Serialize, Clone, JsonSchema)]
 pub struct Config {
-    git: GitConfig,
+    /// Where the definitions come from.
+    #[serde(default)]
+    source: SourceConfig,
+    /// Git repository, or list of repositories, the definitions are downloaded from. Required by
+    /// the `git` source.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    git: Option<GitSources>,
+    /// How often the definitions are updated and how failures are retried.
     definition_downloader: DefinitionDownloaderConfig,
+    /// AMQP output the definitions are published to.
     output: OutputConfig,
+    /// Behaviour once a stage exhausted its retries.
+    #[serde(default)]
+    degraded_mode: DegradedModeConfig,
+    /// Endpoint reporting the health of the provider.
+    #[serde(default)]
+    status: StatusConfig,
 }
 
 impl Config {
-    pub fn git(&self) -> GitConfig {
+    pub fn source(&self) -> SourceConfig {
+        self.source.clone()
+    }
+
+    pub fn git(&self) -> Option<GitSources> {
         self.git.clone()
     }
 
@@ -24,4 +47,37 @@ impl Config {
     pub fn output(&self) -> OutputConfig {
         self.output.clone()
     }
+
+    pub fn degraded_mode(&self) -> DegradedModeConfig {
+        self.degraded_mode.clone()
+    }
+
+    pub fn status(&self) -> StatusConfig {
+        self.status.clone()
+    }
+
+    /// Whether moving from `self` to `other` cannot be applied to a running pipeline,
+    /// which is the case whenever the source, the git repository or the degraded mode changes.
+    pub fn requires_restart(&self, other: &Config) -> bool {
+        self.source != other.source
+            || self.git != other.git
+            || self.degraded_mode != other.degraded_mode
+    }
+
+    /// Turns the flat retry keys older configs use into the retry policies which replaced them.
+    pub fn convert_legacy_keys(&mut self) {
+        self.definition_downloader.convert_legacy_keys();
+        self.output.convert_legacy_keys();
+    }
+
+    /// Registers the credentials the configuration may contain, so they never get shown.
+    pub fn register_secrets(&self) {
+        if let Some(git) = &self.git {
+            for git_config in git.configs() {
+                for repository_url in git_config.repository_urls() {
+                    secret::register_uri_password(repository_url.as_str());
+                }
+            }
+        }
+    }
 }
@@ -1,32 +1,173 @@
 use std::io::{Error, ErrorKind};
 
-use crate::config::{config::Config, config_reader::ConfigReader};
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+
+use crate::config::{
+    config::Config, config_format::ConfigFormat, config_reader::ConfigReader, config_value,
+};
+
+/// Key of the section containing the named profiles, each one being a partial configuration
+/// which is deep merged over the rest of the file.
+const PROFILES_KEY: &str = "profiles";
 
 pub struct ConfigFileReader {
     config_path: String,
+    format: Option<ConfigFormat>,
+    profile: Option<String>,
 }
 
 impl ConfigFileReader {
+    /// Creates a reader which detects the format from the extension of `config_path`.
     pub fn new(config_path: &str) -> ConfigFileReader {
         ConfigFileReader {
             config_path: config_path.to_string(),
+            format: None,
+            profile: None,
         }
     }
-}
 
-impl ConfigReader for ConfigFileReader {
-    fn read(&self) -> Result<Config, Error> {
-        match std::fs::read_to_string(self.config_path.as_str()) {
-            Ok(config_json) => match serde_json::de::from_str(config_json.as_str()) {
-                Ok(config) => return Ok(config),
-                Err(error) => {
+    pub fn with_format(config_path: &str, format: ConfigFormat) -> ConfigFileReader {
+        ConfigFileReader {
+            config_path: config_path.to_string(),
+            format: Some(format),
+            profile: None,
+        }
+    }
+
+    /// Selects the profile to be merged over the base configuration.
+    pub fn with_profile(mut self, profile: &str) -> ConfigFileReader {
+        self.profile = Some(profile.to_string());
+        self
+    }
+
+    fn format(&self) -> Result<ConfigFormat, Error> {
+        match self.format {
+            Some(format) => Ok(format),
+            None => ConfigFormat::from_path(self.config_path.as_str()),
+        }
+    }
+
+    fn deserialize<T: DeserializeOwned>(
+        &self,
+        format: ConfigFormat,
+        content: &str,
+    ) -> Result<T, Error> {
+        match format.deserialize(content) {
+            Ok(value) => Ok(value),
+            Err(error) => Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "failed to deserialize config's {} from '{}': {}",
+                    format, self.config_path, error
+                ),
+            )),
+        }
+    }
+
+    /// Merges `profile` over the base configuration contained in `value`.
+    fn merge_profile(&self, mut value: Value, profile: &str) -> Result<Value, Error> {
+        let profiles = match value.as_object_mut() {
+            Some(object) => object.remove(PROFILES_KEY),
+            None => None,
+        };
+
+        let overlay = match profiles {
+            Some(Value::Object(mut profiles)) => match profiles.remove(profile) {
+                Some(overlay) => overlay,
+                None => {
+                    let available: Vec<&String> = profiles.keys().collect();
                     return Err(Error::new(
-                        ErrorKind::InvalidData,
-                        format!("Failed to deserialize config's JSON: {}", error),
+                        ErrorKind::InvalidInput,
+                        format!(
+                            "profile '{}' not found in config file '{}', available profiles: {:?}",
+                            profile, self.config_path, available
+                        ),
                     ));
                 }
             },
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "profile '{}' requested, but config file '{}' has no '{}' section",
+                        profile, self.config_path, PROFILES_KEY
+                    ),
+                ))
+            }
+        };
+
+        config_value::merge(&mut value, overlay);
+
+        Ok(value)
+    }
+
+    fn resolve_profile(&self, value: Value, profile: &str) -> Result<Config, Error> {
+        match config_value::from_value(self.merge_profile(value, profile)?) {
+            Ok(config) => Ok(config),
+            Err(error) => Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "invalid config in '{}' with profile '{}': {}",
+                    self.config_path, profile, error
+                ),
+            )),
+        }
+    }
+
+    fn read_content(&self) -> Result<String, Error> {
+        match std::fs::read_to_string(self.config_path.as_str()) {
+            Ok(content) => Ok(content),
+            Err(error) => Err(Error::new(
+                error.kind(),
+                format!(
+                    "failed to read config file '{}': {}",
+                    self.config_path, error
+                ),
+            )),
+        }
+    }
+}
+
+impl ConfigReader for ConfigFileReader {
+    fn read(&self) -> Result<Config, Error> {
+        let format = self.format()?;
+        let content = self.read_content()?;
+
+        match &self.profile {
+            // Deserializing straight into the config keeps the position of errors within the file.
+            None => {
+                let mut config: Config = self.deserialize(format, content.as_str())?;
+                config.convert_legacy_keys();
+
+                Ok(config)
+            }
+            Some(profile) => {
+                let value: Value = self.deserialize(format, content.as_str())?;
+                self.resolve_profile(value, profile.as_str())
+            }
+        }
+    }
+
+    /// Reads the file with the profile merged, leaving it to the other layers to complete it. A
+    /// missing file reads as an empty configuration.
+    fn read_value(&self) -> Result<Value, Error> {
+        let format = self.format()?;
+
+        let content = match self.read_content() {
+            Ok(content) => content,
+            Err(error) if error.kind() == ErrorKind::NotFound => {
+                log::debug!("{}, reading the config from the other layers only", error);
+                return Ok(Value::Object(Map::new()));
+            }
             Err(error) => return Err(error),
+        };
+
+        let value: Value = self.deserialize(format, content.as_str())?;
+
+        match &self.profile {
+            None => Ok(value),
+            Some(profile) => self.merge_profile(value, profile.as_str()),
         }
     }
 }
@@ -0,0 +1,68 @@
+use std::{
+    fmt,
+    io::{Error, ErrorKind},
+    path::Path,
+};
+
+use clap::ValueEnum;
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Format of a configuration file.
+#[derive(Debug, Copy, Clone, PartialEq, ValueEnum)]
+pub enum ConfigFormat {
+    Json,
+    Toml,
+    Yaml,
+}
+
+impl ConfigFormat {
+    /// Detects the format from the extension of `path`.
+    pub fn from_path(path: &str) -> Result<ConfigFormat, Error> {
+        let extension = Path::new(path)
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| extension.to_lowercase());
+
+        match extension.as_deref() {
+            Some("json") => Ok(ConfigFormat::Json),
+            Some("toml") => Ok(ConfigFormat::Toml),
+            Some("yaml") | Some("yml") => Ok(ConfigFormat::Yaml),
+            _ => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "cannot detect the format of config file '{}', expected a .json, .toml, .yaml or .yml extension",
+                    path
+                ),
+            )),
+        }
+    }
+
+    /// Deserializes `content`, reporting the position of the failure within the content on error.
+    pub fn deserialize<T: DeserializeOwned>(&self, content: &str) -> Result<T, String> {
+        match self {
+            ConfigFormat::Json => serde_json::from_str(content).map_err(|error| error.to_string()),
+            ConfigFormat::Toml => toml::from_str(content).map_err(|error| error.to_string()),
+            ConfigFormat::Yaml => serde_yaml::from_str(content).map_err(|error| error.to_string()),
+        }
+    }
+
+    pub fn serialize<T: Serialize>(&self, value: &T) -> Result<String, String> {
+        match self {
+            ConfigFormat::Json => {
+                serde_json::to_string_pretty(value).map_err(|error| error.to_string())
+            }
+            ConfigFormat::Toml => toml::to_string_pretty(value).map_err(|error| error.to_string()),
+            ConfigFormat::Yaml => serde_yaml::to_string(value).map_err(|error| error.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for ConfigFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigFormat::Json => write!(f, "JSON"),
+            ConfigFormat::Toml => write!(f, "TOML"),
+            ConfigFormat::Yaml => write!(f, "YAML"),
+        }
+    }
+}
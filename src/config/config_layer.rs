@@ -0,0 +1,8 @@
+use std::io::Error;
+
+use serde_json::Value;
+
+/// Partial configuration source that is applied on top of an already read configuration.
+pub trait ConfigLayer: Send + Sync {
+    fn apply(&self, config: &mut Value) -> Result<(), Error>;
+}
@@ -0,0 +1,63 @@
+use std::{io::Error, str::FromStr};
+
+use serde_json::Value;
+
+use crate::config::{config_layer::ConfigLayer, config_value};
+
+const KEY_SEPARATOR: char = '.';
+
+/// Single `key=value` override of a configuration value, where `key` is a dot separated path
+/// such as `output.amqp_channel_name`.
+#[derive(Debug, Clone)]
+pub struct ConfigOverride {
+    key: String,
+    value: String,
+}
+
+impl ConfigOverride {
+    pub fn new(key: &str, value: &str) -> ConfigOverride {
+        ConfigOverride {
+            key: key.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        self.key.as_str()
+    }
+
+    /// Writes the override into the serialized configuration.
+    pub fn apply(&self, config: &mut Value) -> Result<(), Error> {
+        let path: Vec<String> = self
+            .key
+            .split(KEY_SEPARATOR)
+            .map(|segment| segment.to_string())
+            .collect();
+
+        config_value::set(config, path.as_slice(), self.value.as_str(), false)
+    }
+}
+
+impl FromStr for ConfigOverride {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once('=') {
+            Some((key, value)) if !key.trim().is_empty() => {
+                Ok(ConfigOverride::new(key.trim(), value))
+            }
+            _ => Err(format!("expected 'key=value', got '{}'", s)),
+        }
+    }
+}
+
+/// Overrides are applied in order, so later overrides win.
+impl ConfigLayer for Vec<ConfigOverride> {
+    fn apply(&self, config: &mut Value) -> Result<(), Error> {
+        for config_override in self {
+            config_override.apply(config)?;
+        }
+
+        Ok(())
+    }
+}
@@ -1,7 +1,15 @@
 use std::io::Error;
 
-use crate::config::config::Config;
+use serde_json::Value;
 
-pub trait ConfigReader {
+use crate::config::{config::Config, config_value};
+
+pub trait ConfigReader: Send + Sync {
     fn read(&self) -> Result<Config, Error>;
+
+    /// Reads the configuration as a value, which may still lack keys that layers applied on top
+    /// of it provide.
+    fn read_value(&self) -> Result<Value, Error> {
+        config_value::to_value(self.read()?)
+    }
 }
@@ -1,7 +1,39 @@
-use super::{config_file_reader::ConfigFileReader, config_reader::ConfigReader};
+use super::{
+    config_file_reader::ConfigFileReader,
+    config_format::ConfigFormat,
+    config_override::ConfigOverride,
+    config_reader::ConfigReader,
+    env_config_reader::{EnvConfigReader, DEFAULT_ENV_PREFIX},
+    layered_config_reader::LayeredConfigReader,
+};
 
-const DEFAULT_CONFIG_PATH: &str = "./config.json";
+pub const DEFAULT_CONFIG_PATH: &str = "./config.json";
 
-pub fn default() -> Box<dyn ConfigReader> {
-    Box::new(ConfigFileReader::new(DEFAULT_CONFIG_PATH))
+/// Reads the config file located at `config_path`, then applies the environment variables
+/// and finally the command-line `overrides` on top of it.
+///
+/// The format of the file is detected from its extension, unless `format` is set.
+/// If `profile` is set, that profile of the file is merged over its base configuration.
+pub fn build(
+    config_path: &str,
+    format: Option<ConfigFormat>,
+    profile: Option<&str>,
+    overrides: Vec<ConfigOverride>,
+) -> Box<dyn ConfigReader> {
+    let mut file_reader = match format {
+        Some(format) => ConfigFileReader::with_format(config_path, format),
+        None => ConfigFileReader::new(config_path),
+    };
+
+    if let Some(profile) = profile {
+        file_reader = file_reader.with_profile(profile);
+    }
+
+    Box::new(LayeredConfigReader::new(
+        Box::new(file_reader),
+        vec![
+            Box::new(EnvConfigReader::new(DEFAULT_ENV_PREFIX)),
+            Box::new(overrides),
+        ],
+    ))
 }
@@ -0,0 +1,110 @@
+use std::sync::OnceLock;
+
+use serde_json::Value;
+
+use crate::config::config::Config;
+
+const REFERENCE_PREFIX: &str = "#/definitions/";
+
+static SCHEMA: OnceLock<Value> = OnceLock::new();
+
+/// JSON schema of the configuration, as printed by `config schema`.
+pub fn schema() -> &'static Value {
+    SCHEMA.get_or_init(|| {
+        serde_json::to_value(schemars::schema_for!(Config))
+            .expect("config schema is always serializable")
+    })
+}
+
+/// JSON types the value found at `path` may take, such as `string` or `integer`.
+///
+/// Returns `None` if the schema does not know `path`, and an empty list if it does not restrict
+/// the type of the value.
+pub fn value_types(path: &[String]) -> Option<Vec<String>> {
+    let schema = schema();
+    let mut nodes = variants(schema, schema);
+
+    for segment in path {
+        nodes = nodes
+            .into_iter()
+            .filter_map(|node| {
+                node.get("properties")
+                    .and_then(|properties| properties.get(segment.as_str()))
+                    .or_else(|| node.get("additionalProperties").filter(|v| v.is_object()))
+            })
+            .flat_map(|node| variants(schema, node))
+            .collect();
+
+        if nodes.is_empty() {
+            return None;
+        }
+    }
+
+    let mut types: Vec<String> = Vec::new();
+
+    for node in nodes {
+        let node_types = match node.get("type") {
+            Some(Value::String(value_type)) => vec![value_type.clone()],
+            Some(Value::Array(value_types)) => value_types
+                .iter()
+                .filter_map(|value_type| value_type.as_str().map(str::to_string))
+                .collect(),
+            _ => node
+                .get("enum")
+                .and_then(Value::as_array)
+                .map(|values| values.iter().map(json_type).collect())
+                .unwrap_or_default(),
+        };
+
+        for node_type in node_types {
+            if !types.contains(&node_type) {
+                types.push(node_type);
+            }
+        }
+    }
+
+    Some(types)
+}
+
+/// Whether `path` is a key of the configuration.
+pub fn is_known(path: &[String]) -> bool {
+    value_types(path).is_some()
+}
+
+/// Schemas `node` stands for once references are resolved and `allOf`, `anyOf` and `oneOf` are
+/// expanded.
+fn variants<'a>(schema: &'a Value, node: &'a Value) -> Vec<&'a Value> {
+    if let Some(reference) = node.get("$ref").and_then(Value::as_str) {
+        return match reference
+            .strip_prefix(REFERENCE_PREFIX)
+            .and_then(|name| schema.get("definitions")?.get(name))
+        {
+            Some(definition) => variants(schema, definition),
+            None => Vec::new(),
+        };
+    }
+
+    let mut nodes = vec![node];
+
+    for keyword in ["allOf", "anyOf", "oneOf"] {
+        if let Some(subschemas) = node.get(keyword).and_then(Value::as_array) {
+            for subschema in subschemas {
+                nodes.extend(variants(schema, subschema));
+            }
+        }
+    }
+
+    nodes
+}
+
+fn json_type(value: &Value) -> String {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+    .to_string()
+}
@@ -0,0 +1,496 @@
+use std::{
+    fmt,
+    path::{Component, Path, PathBuf},
+};
+
+use semver::VersionReq;
+
+use crate::{
+    config::{
+        config::Config,
+        definition_downloader_config::{DefinitionDownloaderConfig, UpdateMode},
+        freeze_window_config::FreezeWindowConfig,
+        git_config::{GitConfig, GitPin, GitSources},
+        retry_policy_config::RetryPolicyConfig,
+        source_config::SourceConfig,
+    },
+    update_schedule,
+};
+
+/// Shortest abbreviated commit hash accepted as a pin.
+const MIN_COMMIT_HASH_LENGTH: usize = 7;
+
+/// Single problem found within the configuration, located by the dot separated path of the field.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigViolation {
+    pub field: String,
+    pub message: String,
+}
+
+impl ConfigViolation {
+    pub fn new(field: &str, message: &str) -> ConfigViolation {
+        ConfigViolation {
+            field: field.to_string(),
+            message: message.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ConfigViolation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
+    }
+}
+
+/// Every violation found within a configuration.
+#[derive(Debug)]
+pub struct ConfigValidationError {
+    pub violations: Vec<ConfigViolation>,
+}
+
+impl fmt::Display for ConfigValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "invalid config, {} problem(s) found",
+            self.violations.len()
+        )?;
+
+        for violation in &self.violations {
+            write!(f, "\n  {}", violation)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Checks the semantics of a deserialized configuration, collecting every violation instead of
+/// stopping at the first one.
+///
+/// `categories_path` is the directory the definitions are read from.
+pub fn validate(config: &Config, categories_path: &str) -> Result<(), ConfigValidationError> {
+    let mut violations: Vec<ConfigViolation> = Vec::new();
+
+    match config.source() {
+        SourceConfig::Git => match config.git() {
+            Some(git) => validate_git(&mut violations, &git, categories_path),
+            None => violations.push(ConfigViolation::new(
+                "git",
+                "must be set when source.type is 'git'",
+            )),
+        },
+        SourceConfig::LocalDirectory(local_directory) => {
+            require_not_empty(&mut violations, "source.path", &local_directory.path);
+
+            if let Some(version) = &local_directory.version {
+                require_not_empty(&mut violations, "source.version", version);
+            }
+        }
+        SourceConfig::Archive(archive) => {
+            require_not_empty(&mut violations, "source.path", &archive.path);
+            require_not_empty(&mut violations, "source.staging_dir", &archive.staging_dir);
+
+            if !archive.staging_dir.trim().is_empty()
+                && overlaps(&archive.staging_dir, &archive.path)
+            {
+                violations.push(ConfigViolation::new(
+                    "source.staging_dir",
+                    "must neither contain nor be within source.path, as it is replaced on every unpack",
+                ));
+            }
+        }
+    }
+
+    let definition_downloader = config.definition_downloader();
+    if definition_downloader.update_interval_seconds == 0 {
+        violations.push(ConfigViolation::new(
+            "definition_downloader.update_interval_seconds",
+            "must be greater than 0",
+        ));
+    }
+    if let Some(adaptive_polling) = &definition_downloader.adaptive_polling {
+        if adaptive_polling.min_interval_seconds == 0
+            || adaptive_polling.min_interval_seconds > definition_downloader.update_interval_seconds
+        {
+            violations.push(ConfigViolation::new(
+                "definition_downloader.adaptive_polling.min_interval_seconds",
+                format!(
+                    "must be between 1 and update_interval_seconds, {}",
+                    definition_downloader.update_interval_seconds
+                )
+                .as_str(),
+            ));
+        }
+
+        // Also rejects NaN.
+        if adaptive_polling.multiplier.is_nan() || adaptive_polling.multiplier < 1.0 {
+            violations.push(ConfigViolation::new(
+                "definition_downloader.adaptive_polling.multiplier",
+                format!("must be at least 1, got {}", adaptive_polling.multiplier).as_str(),
+            ));
+        }
+    }
+    validate_update_schedule(&mut violations, &definition_downloader);
+    if definition_downloader.download_timeout_seconds == 0 {
+        violations.push(ConfigViolation::new(
+            "definition_downloader.download_timeout_seconds",
+            "must be greater than 0",
+        ));
+    }
+    if definition_downloader.update_timeout_seconds == 0 {
+        violations.push(ConfigViolation::new(
+            "definition_downloader.update_timeout_seconds",
+            "must be greater than 0",
+        ));
+    }
+    validate_retry_policy(
+        &mut violations,
+        "definition_downloader.download_retry",
+        &definition_downloader.download_retry,
+    );
+    validate_retry_policy(
+        &mut violations,
+        "definition_downloader.update_retry",
+        &definition_downloader.update_retry,
+    );
+
+    let output = config.output();
+    require_not_empty(
+        &mut violations,
+        "output.amqp_channel_name",
+        &output.amqp_channel_name,
+    );
+    validate_retry_policy(
+        &mut violations,
+        "output.connection_retry",
+        &output.connection_retry,
+    );
+    validate_retry_policy(&mut violations, "output.set_retry", &output.set_retry);
+
+    if config.degraded_mode().retry_interval_seconds == 0 {
+        violations.push(ConfigViolation::new(
+            "degraded_mode.retry_interval_seconds",
+            "must be greater than 0",
+        ));
+    }
+
+    if let Some(listen_address) = config.status().listen_address {
+        require_not_empty(&mut violations, "status.listen_address", &listen_address);
+    }
+
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(ConfigValidationError { violations })
+    }
+}
+
+/// Checks the cron expressions of the scheduled updates and the freeze windows.
+fn validate_update_schedule(
+    violations: &mut Vec<ConfigViolation>,
+    definition_downloader: &DefinitionDownloaderConfig,
+) {
+    for (index, expression) in definition_downloader.update_schedule.iter().enumerate() {
+        if let Err(error) = update_schedule::parse_cron(expression) {
+            violations.push(ConfigViolation::new(
+                format!("definition_downloader.update_schedule[{}]", index).as_str(),
+                error.as_str(),
+            ));
+        }
+    }
+
+    if !definition_downloader.update_schedule.is_empty() {
+        if definition_downloader.update_mode != UpdateMode::Poll {
+            violations.push(ConfigViolation::new(
+                "definition_downloader.update_schedule",
+                "requires update_mode to be 'poll'",
+            ));
+        }
+
+        if definition_downloader.adaptive_polling.is_some() {
+            violations.push(ConfigViolation::new(
+                "definition_downloader.update_schedule",
+                "cannot be combined with adaptive_polling",
+            ));
+        }
+    }
+
+    for (index, freeze_window) in definition_downloader.freeze_windows.iter().enumerate() {
+        let field = format!("definition_downloader.freeze_windows[{}]", index);
+
+        match freeze_window {
+            FreezeWindowConfig::Period { start, end } => {
+                let start_time = update_schedule::parse_timestamp(start);
+                let end_time = update_schedule::parse_timestamp(end);
+
+                match (start_time, end_time) {
+                    (Ok(start_time), Ok(end_time)) if start_time >= end_time => {
+                        violations.push(ConfigViolation::new(
+                            format!("{}.period.end", field).as_str(),
+                            "must be after start",
+                        ))
+                    }
+                    (start_time, end_time) => {
+                        if let Err(error) = start_time {
+                            violations.push(ConfigViolation::new(
+                                format!("{}.period.start", field).as_str(),
+                                error.as_str(),
+                            ));
+                        }
+                        if let Err(error) = end_time {
+                            violations.push(ConfigViolation::new(
+                                format!("{}.period.end", field).as_str(),
+                                error.as_str(),
+                            ));
+                        }
+                    }
+                }
+            }
+            FreezeWindowConfig::Recurring {
+                start,
+                duration_minutes,
+            } => {
+                if let Err(error) = update_schedule::parse_cron(start) {
+                    violations.push(ConfigViolation::new(
+                        format!("{}.recurring.start", field).as_str(),
+                        error.as_str(),
+                    ));
+                }
+                if *duration_minutes == 0 {
+                    violations.push(ConfigViolation::new(
+                        format!("{}.recurring.duration_minutes", field).as_str(),
+                        "must be greater than 0",
+                    ));
+                }
+            }
+        }
+    }
+}
+
+/// Checks the git repositories. A single repository must be cloned into `categories_path`, while
+/// several repositories must be cloned into distinct directories.
+fn validate_git(violations: &mut Vec<ConfigViolation>, git: &GitSources, categories_path: &str) {
+    match git {
+        GitSources::Single(git_config) => {
+            validate_git_config(violations, "git", git_config);
+
+            if !git_config.repository_local_dir.trim().is_empty()
+                && normalize(&git_config.repository_local_dir) != normalize(categories_path)
+            {
+                violations.push(ConfigViolation::new(
+                    "git.repository_local_dir",
+                    format!(
+                        "must be the directory the definitions are read from, '{}'",
+                        categories_path
+                    )
+                    .as_str(),
+                ));
+            }
+        }
+        GitSources::Multiple(git_configs) => {
+            if git_configs.is_empty() {
+                violations.push(ConfigViolation::new(
+                    "git",
+                    "must contain at least one repository",
+                ));
+            }
+
+            let mut local_dirs: Vec<PathBuf> = Vec::new();
+            for (index, git_config) in git_configs.iter().enumerate() {
+                let field = format!("git[{}]", index);
+                validate_git_config(violations, field.as_str(), git_config);
+
+                let local_dir = normalize(&git_config.repository_local_dir);
+                if local_dirs.contains(&local_dir) {
+                    violations.push(ConfigViolation::new(
+                        format!("{}.repository_local_dir", field).as_str(),
+                        "must differ from the directories of the other repositories",
+                    ));
+                }
+                local_dirs.push(local_dir);
+            }
+        }
+    }
+}
+
+/// Checks a single git repository, whose fields are located under `field`.
+fn validate_git_config(violations: &mut Vec<ConfigViolation>, field: &str, git: &GitConfig) {
+    require_not_empty(
+        violations,
+        format!("{}.repository_url", field).as_str(),
+        &git.repository_url,
+    );
+    require_not_empty(
+        violations,
+        format!("{}.repository_local_dir", field).as_str(),
+        &git.repository_local_dir,
+    );
+    require_not_empty(
+        violations,
+        format!("{}.remote_name", field).as_str(),
+        &git.remote_name,
+    );
+    require_not_empty(
+        violations,
+        format!("{}.remote_branch", field).as_str(),
+        &git.remote_branch,
+    );
+
+    match &git.pin {
+        Some(GitPin::Tag(tag)) => {
+            require_not_empty(violations, format!("{}.pin.tag", field).as_str(), tag)
+        }
+        Some(GitPin::Commit(commit)) if !is_commit_hash(commit) => {
+            violations.push(ConfigViolation::new(
+                format!("{}.pin.commit", field).as_str(),
+                format!(
+                    "must be a commit hash of at least {} hexadecimal characters",
+                    MIN_COMMIT_HASH_LENGTH
+                )
+                .as_str(),
+            ))
+        }
+        Some(GitPin::Version(requirement)) => {
+            if let Err(error) = VersionReq::parse(requirement) {
+                violations.push(ConfigViolation::new(
+                    format!("{}.pin.version", field).as_str(),
+                    format!("must be a semver requirement: {}", error).as_str(),
+                ));
+            }
+        }
+        _ => (),
+    }
+
+    for (index, mirror_url) in git.mirror_urls.iter().enumerate() {
+        let mirror_field = format!("{}.mirror_urls[{}]", field, index);
+        require_not_empty(violations, mirror_field.as_str(), mirror_url);
+
+        if git
+            .repository_urls()
+            .iter()
+            .filter(|url| *url == mirror_url)
+            .count()
+            > 1
+        {
+            violations.push(ConfigViolation::new(
+                mirror_field.as_str(),
+                "must differ from repository_url and the other mirrors",
+            ));
+        }
+    }
+
+    validate_git_credentials(violations, field, git);
+
+    if let Some(signature_verification) = &git.signature_verification {
+        require_not_empty(
+            violations,
+            format!("{}.signature_verification.keyring", field).as_str(),
+            &signature_verification.keyring,
+        );
+    }
+}
+
+fn validate_git_credentials(violations: &mut Vec<ConfigViolation>, field: &str, git: &GitConfig) {
+    let files = [
+        ("password_file", &git.password_file),
+        ("ssh_key_file", &git.ssh_key_file),
+        ("ssh_passphrase_file", &git.ssh_passphrase_file),
+        ("known_hosts_file", &git.known_hosts_file),
+    ];
+
+    for (name, file) in files {
+        if let Some(file) = file {
+            require_not_empty(violations, format!("{}.{}", field, name).as_str(), file);
+        }
+    }
+
+    if git.ssh_passphrase_file.is_some() && git.ssh_key_file.is_none() {
+        violations.push(ConfigViolation::new(
+            format!("{}.ssh_passphrase_file", field).as_str(),
+            "requires ssh_key_file to be set",
+        ));
+    }
+
+    if !git.uses_ssh() {
+        for (name, file) in [
+            ("ssh_key_file", &git.ssh_key_file),
+            ("known_hosts_file", &git.known_hosts_file),
+        ] {
+            if file.is_some() {
+                violations.push(ConfigViolation::new(
+                    format!("{}.{}", field, name).as_str(),
+                    "requires repository_url or one of mirror_urls to be an SSH URL",
+                ));
+            }
+        }
+    }
+}
+
+fn is_commit_hash(commit: &str) -> bool {
+    commit.len() >= MIN_COMMIT_HASH_LENGTH && commit.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+fn require_not_empty(violations: &mut Vec<ConfigViolation>, field: &str, value: &str) {
+    if value.trim().is_empty() {
+        violations.push(ConfigViolation::new(field, "must not be empty"));
+    }
+}
+
+fn validate_retry_policy(
+    violations: &mut Vec<ConfigViolation>,
+    field: &str,
+    retry_policy: &RetryPolicyConfig,
+) {
+    if retry_policy.max_interval_seconds < retry_policy.initial_interval_seconds {
+        violations.push(ConfigViolation::new(
+            format!("{}.max_interval_seconds", field).as_str(),
+            format!(
+                "must not be lower than initial_interval_seconds, {}",
+                retry_policy.initial_interval_seconds
+            )
+            .as_str(),
+        ));
+    }
+
+    // Also rejects NaN.
+    if retry_policy.multiplier.is_nan() || retry_policy.multiplier < 1.0 {
+        violations.push(ConfigViolation::new(
+            format!("{}.multiplier", field).as_str(),
+            format!("must be at least 1, got {}", retry_policy.multiplier).as_str(),
+        ));
+    }
+
+    if !(0.0..=1.0).contains(&retry_policy.jitter) {
+        violations.push(ConfigViolation::new(
+            format!("{}.jitter", field).as_str(),
+            format!("must be between 0 and 1, got {}", retry_policy.jitter).as_str(),
+        ));
+    }
+
+    if retry_policy.deadline_seconds == Some(0) {
+        violations.push(ConfigViolation::new(
+            format!("{}.deadline_seconds", field).as_str(),
+            "must be greater than 0",
+        ));
+    }
+}
+
+/// Whether one of the paths is, or is within, the other one, relative paths being resolved against
+/// the working directory.
+fn overlaps(path: &str, other_path: &str) -> bool {
+    let absolute = |path: &str| match std::env::current_dir() {
+        Ok(current_dir) => current_dir.join(normalize(path)),
+        Err(_) => normalize(path),
+    };
+    let (path, other_path) = (absolute(path), absolute(other_path));
+
+    path.starts_with(&other_path) || other_path.starts_with(&path)
+}
+
+/// Drops `.` components and trailing separators, so that `./categories/` equals `categories`.
+fn normalize(path: &str) -> PathBuf {
+    Path::new(path)
+        .components()
+        .filter(|component| !matches!(component, Component::CurDir))
+        .collect()
+}
@@ -0,0 +1,118 @@
+use std::io::{Error, ErrorKind};
+
+use serde_json::{Map, Value};
+
+use crate::config::{config::Config, config_schema};
+
+pub fn to_value(config: Config) -> Result<Value, Error> {
+    match serde_json::to_value(config) {
+        Ok(value) => Ok(value),
+        Err(error) => Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("failed to serialize config: {}", error),
+        )),
+    }
+}
+
+pub fn from_value(value: Value) -> Result<Config, Error> {
+    match serde_json::from_value::<Config>(value) {
+        Ok(mut config) => {
+            config.convert_legacy_keys();
+            Ok(config)
+        }
+        Err(error) => Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("failed to deserialize config: {}", error),
+        )),
+    }
+}
+
+/// Sets the value found at `path` to `raw_value`.
+///
+/// The value is parsed according to the type the schema expects at `path`: it is kept as a
+/// string wherever a string is accepted, otherwise it is parsed as JSON. Keys the schema does not
+/// know keep the previous behaviour of staying a string if the current value is a string, and of
+/// falling back to a string if they are not valid JSON.
+/// Missing keys are created if the schema knows them or `create_missing` is set, otherwise they
+/// are reported as unknown.
+pub fn set(
+    config: &mut Value,
+    path: &[String],
+    raw_value: &str,
+    create_missing: bool,
+) -> Result<(), Error> {
+    let value_types = config_schema::value_types(path);
+
+    if value_types.is_none() && !create_missing {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("unknown config key '{}'", path.join(".")),
+        ));
+    }
+
+    let mut current = config;
+
+    for segment in path {
+        if current.is_null() {
+            *current = Value::Object(Map::new());
+        }
+
+        current = match current {
+            Value::Object(map) => map.entry(segment.clone()).or_insert(Value::Null),
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "config key '{}' is not an object, cannot set '{}'",
+                        segment,
+                        path.join(".")
+                    ),
+                ))
+            }
+        };
+    }
+
+    *current = match value_types {
+        Some(value_types) if value_types.iter().any(|value_type| value_type == "string") => {
+            Value::String(raw_value.to_string())
+        }
+        Some(value_types) if !value_types.is_empty() => match serde_json::from_str(raw_value) {
+            Ok(value) => value,
+            Err(_) => {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "config key '{}' expects {}, got '{}'",
+                        path.join("."),
+                        value_types.join(" or "),
+                        raw_value
+                    ),
+                ))
+            }
+        },
+        _ if current.is_string() => Value::String(raw_value.to_string()),
+        _ => match serde_json::from_str(raw_value) {
+            Ok(value) => value,
+            Err(_) => Value::String(raw_value.to_string()),
+        },
+    };
+
+    Ok(())
+}
+
+/// Deep merges `overlay` into `base`: objects are merged key by key, any other value is replaced.
+pub fn merge(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Object(base), Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(key.as_str()) {
+                    Some(base_value) => merge(base_value, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
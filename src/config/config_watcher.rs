@@ -0,0 +1,140 @@
+use std::{
+    io::Error,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use tokio::{
+    signal::unix::{signal, SignalKind},
+    sync::{mpsc, watch::Sender},
+    time::sleep,
+};
+
+use crate::config::{config::Config, config_reader::ConfigReader, config_validator};
+
+/// Time to wait for a burst of file system events to settle before reloading.
+const DEBOUNCE_DURATION: Duration = Duration::from_millis(500);
+
+/// Reloads the configuration whenever its file changes or the process receives `SIGHUP`,
+/// publishing every valid configuration which differs from the current one.
+pub struct ConfigWatcher {
+    reader: Box<dyn ConfigReader>,
+    config_path: PathBuf,
+    categories_path: String,
+    config_sender: Sender<Config>,
+}
+
+impl ConfigWatcher {
+    pub fn new(
+        reader: Box<dyn ConfigReader>,
+        config_path: &str,
+        categories_path: &str,
+        config_sender: Sender<Config>,
+    ) -> ConfigWatcher {
+        ConfigWatcher {
+            reader,
+            config_path: PathBuf::from(config_path),
+            categories_path: categories_path.to_string(),
+            config_sender,
+        }
+    }
+
+    pub async fn run(&self) -> Result<(), Error> {
+        let (event_sender, mut event_receiver) = mpsc::unbounded_channel::<()>();
+
+        // Editors usually replace the file instead of writing into it, so the parent directory is watched.
+        let config_file_name = self.config_path.file_name().map(|name| name.to_owned());
+        let mut watcher = match RecommendedWatcher::new(
+            move |result: notify::Result<notify::Event>| {
+                if let Ok(event) = result {
+                    if event
+                        .paths
+                        .iter()
+                        .any(|path| path.file_name() == config_file_name.as_deref())
+                    {
+                        let _ = event_sender.send(());
+                    }
+                }
+            },
+            notify::Config::default(),
+        ) {
+            Ok(watcher) => watcher,
+            Err(error) => {
+                return Err(Error::other(format!(
+                    "failed to create config watcher: {}",
+                    error
+                )))
+            }
+        };
+
+        let watched_directory = match self.config_path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+
+        if let Err(error) = watcher.watch(watched_directory, RecursiveMode::NonRecursive) {
+            return Err(Error::other(format!(
+                "failed to watch config file '{}': {}",
+                self.config_path.display(),
+                error
+            )));
+        }
+
+        let mut hangup = signal(SignalKind::hangup())?;
+
+        loop {
+            tokio::select! {
+                event = event_receiver.recv() => {
+                    if event.is_none() {
+                        return Ok(());
+                    }
+
+                    sleep(DEBOUNCE_DURATION).await;
+                    while event_receiver.try_recv().is_ok() {}
+
+                    log::info!("config file changed, reloading config");
+                }
+                _ = hangup.recv() => {
+                    log::info!("received SIGHUP, reloading config");
+                }
+            }
+
+            self.reload();
+        }
+    }
+
+    fn reload(&self) {
+        let config = match self.reader.read() {
+            Ok(config) => config,
+            Err(error) => {
+                log::error!(
+                    "failed to reload config, keeping the current one: {}",
+                    error
+                );
+                return;
+            }
+        };
+
+        // The reloaded config may bring credentials of its own, which must never be logged.
+        config.register_secrets();
+
+        if let Err(error) = config_validator::validate(&config, self.categories_path.as_str()) {
+            log::error!(
+                "reloaded config is invalid, keeping the current one: {}",
+                error
+            );
+            return;
+        }
+
+        self.config_sender.send_if_modified(|current| {
+            if serde_json::to_value(&*current).ok() == serde_json::to_value(&config).ok() {
+                log::info!("reloaded config is unchanged");
+                return false;
+            }
+
+            *current = config;
+            true
+        });
+    }
+}
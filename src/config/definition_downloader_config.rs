@@ -1,12 +1,111 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-#[derive(Deserialize, Serialize, Clone)]
+use super::{
+    adaptive_polling_config::AdaptivePollingConfig,
+    freeze_window_config::FreezeWindowConfig,
+    retry_policy_config::{self, RetryPolicyConfig},
+};
+
+/// How changes of the definitions are noticed once downloaded.
+#[derive(Debug, Default, Deserialize, Serialize, Clone, Copy, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum UpdateMode {
+    /// Refreshes the source every `update_interval_seconds`.
+    #[default]
+    Poll,
+    /// Refreshes the source whenever a file changes within the path it reports as watched, such
+    /// as the definitions directory or the archive drop directory.
+    Watch,
+}
+
+#[derive(Deserialize, Serialize, Clone, PartialEq, JsonSchema)]
 pub struct DefinitionDownloaderConfig {
+    /// Whether the source is polled or watched for changes.
+    #[serde(default)]
+    pub update_mode: UpdateMode,
+    /// Milliseconds to wait for a burst of file changes to settle while watching.
+    #[serde(default = "default_watch_debounce_milliseconds")]
+    pub watch_debounce_milliseconds: u64,
+
+    /// Seconds between two updates of the definitions.
+    #[schemars(range(min = 1))]
     pub update_interval_seconds: u64,
+    /// Adapts the interval between two updates to how often the definitions change, using
+    /// `update_interval_seconds` as the longest interval. Disabled if not set.
+    #[serde(default)]
+    pub adaptive_polling: Option<AdaptivePollingConfig>,
+    /// Cron expressions of the times the source is polled at, such as `0 6 * * Mon-Fri`, instead
+    /// of every `update_interval_seconds`. Evaluated in UTC.
+    #[serde(default)]
+    pub update_schedule: Vec<String>,
+    /// Periods during which the source is only checked for changes, which are downloaded and
+    /// applied once the period ends. A restart within one keeps the definitions served before.
+    #[serde(default)]
+    pub freeze_windows: Vec<FreezeWindowConfig>,
 
-    pub download_retry_count: i32,
-    pub download_retry_interval_seconds: u64,
+    /// Retries of the initial download.
+    #[serde(default)]
+    pub download_retry: RetryPolicyConfig,
+    /// Whether to start with the definitions left behind by a previous run if the initial download
+    /// fails, such as a clone whose remote is unreachable, while updating them in the background.
+    #[serde(default)]
+    pub offline_start: bool,
+    /// Retries of an update, which is given up until the next update once exhausted.
+    #[serde(default)]
+    pub update_retry: RetryPolicyConfig,
+
+    /// Seconds a single download attempt may take before being given up.
+    #[serde(default = "default_download_timeout_seconds")]
+    #[schemars(range(min = 1))]
+    pub download_timeout_seconds: u64,
+    /// Seconds a single update attempt may take before being given up.
+    #[serde(default = "default_update_timeout_seconds")]
+    #[schemars(range(min = 1))]
+    pub update_timeout_seconds: u64,
+
+    /// Deprecated, use `download_retry` instead.
+    #[serde(default, skip_serializing)]
+    pub download_retry_count: Option<u32>,
+    /// Deprecated, use `download_retry` instead.
+    #[serde(default, skip_serializing)]
+    pub download_retry_interval_seconds: Option<u64>,
+    /// Deprecated, use `update_retry` instead.
+    #[serde(default, skip_serializing)]
+    pub update_retry_count: Option<u32>,
+    /// Deprecated, use `update_retry` instead.
+    #[serde(default, skip_serializing)]
+    pub update_retry_interval_seconds: Option<u64>,
+}
+
+impl DefinitionDownloaderConfig {
+    /// Turns the flat retry keys replaced by the retry policies into the policies.
+    pub fn convert_legacy_keys(&mut self) {
+        retry_policy_config::convert_legacy_keys(
+            "definition_downloader",
+            "download_retry",
+            &mut self.download_retry,
+            self.download_retry_count.take(),
+            self.download_retry_interval_seconds.take(),
+        );
+        retry_policy_config::convert_legacy_keys(
+            "definition_downloader",
+            "update_retry",
+            &mut self.update_retry,
+            self.update_retry_count.take(),
+            self.update_retry_interval_seconds.take(),
+        );
+    }
+}
+
+fn default_watch_debounce_milliseconds() -> u64 {
+    500
+}
+
+fn default_download_timeout_seconds() -> u64 {
+    600
+}
 
-    pub update_retry_count: i32,
-    pub update_retry_interval_seconds: u64,
+fn default_update_timeout_seconds() -> u64 {
+    300
 }
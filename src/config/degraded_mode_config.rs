@@ -0,0 +1,27 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// What the provider does once a stage exhausted its retries.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, JsonSchema)]
+pub struct DegradedModeConfig {
+    /// Exit with a failure code instead of staying up in degraded mode.
+    #[serde(default)]
+    pub exit_on_exhausted_retries: bool,
+    /// Seconds between two attempts while degraded.
+    #[serde(default = "default_retry_interval_seconds")]
+    #[schemars(range(min = 1))]
+    pub retry_interval_seconds: u64,
+}
+
+fn default_retry_interval_seconds() -> u64 {
+    900
+}
+
+impl Default for DegradedModeConfig {
+    fn default() -> Self {
+        DegradedModeConfig {
+            exit_on_exhausted_retries: false,
+            retry_interval_seconds: default_retry_interval_seconds(),
+        }
+    }
+}
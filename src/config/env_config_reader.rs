@@ -0,0 +1,85 @@
+use std::io::Error;
+
+use serde_json::{Map, Value};
+
+use crate::config::{
+    config::Config, config_layer::ConfigLayer, config_reader::ConfigReader, config_schema,
+    config_value,
+};
+
+pub const DEFAULT_ENV_PREFIX: &str = "COOPLAN_";
+const LEVEL_SEPARATOR: &str = "__";
+
+/// Reads configuration values from environment variables such as `COOPLAN_OUTPUT__SET_RETRY_COUNT`,
+/// where `__` separates the nesting levels of the configuration.
+///
+/// Variables without a level separator, like `COOPLAN_PROFILE`, are not configuration values.
+pub struct EnvConfigReader {
+    prefix: String,
+}
+
+impl EnvConfigReader {
+    pub fn new(prefix: &str) -> EnvConfigReader {
+        EnvConfigReader {
+            prefix: prefix.to_string(),
+        }
+    }
+
+    fn variables(&self) -> Vec<(Vec<String>, String)> {
+        let mut variables: Vec<(Vec<String>, String)> = std::env::vars()
+            .filter_map(|(name, value)| {
+                let key = name.strip_prefix(self.prefix.as_str())?;
+
+                if !key.contains(LEVEL_SEPARATOR) {
+                    return None;
+                }
+
+                let path = key
+                    .split(LEVEL_SEPARATOR)
+                    .map(|segment| segment.to_lowercase())
+                    .collect();
+
+                Some((path, value))
+            })
+            .collect();
+
+        // Deterministic order, so that the same environment always produces the same config.
+        variables.sort();
+
+        variables
+    }
+
+    fn apply_variables(&self, config: &mut Value, create_missing: bool) -> Result<(), Error> {
+        for (path, value) in self.variables() {
+            if !create_missing && !config_schema::is_known(path.as_slice()) {
+                log::warn!(
+                    "ignoring environment variable {}{}, '{}' is not a config key",
+                    self.prefix,
+                    path.join(LEVEL_SEPARATOR).to_uppercase(),
+                    path.join(".")
+                );
+                continue;
+            }
+
+            config_value::set(config, path.as_slice(), value.as_str(), create_missing)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Builds the whole configuration from environment variables alone.
+impl ConfigReader for EnvConfigReader {
+    fn read(&self) -> Result<Config, Error> {
+        let mut config = Value::Object(Map::new());
+        self.apply_variables(&mut config, true)?;
+
+        config_value::from_value(config)
+    }
+}
+
+impl ConfigLayer for EnvConfigReader {
+    fn apply(&self, config: &mut Value) -> Result<(), Error> {
+        self.apply_variables(config, false)
+    }
+}
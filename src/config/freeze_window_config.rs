@@ -0,0 +1,18 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Period during which the definitions must not change. Updates found meanwhile are held until
+/// the period ends.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FreezeWindowConfig {
+    /// Single period between two RFC 3339 timestamps, such as `2024-12-23T00:00:00Z`.
+    Period { start: String, end: String },
+    /// Period starting whenever a cron expression matches, such as `0 0 28 * *` for a month-end
+    /// close, and lasting `duration_minutes`.
+    Recurring {
+        start: String,
+        #[schemars(range(min = 1))]
+        duration_minutes: u64,
+    },
+}
@@ -0,0 +1,155 @@
+use std::io::Error;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::secret;
+
+use super::signature_verification_config::SignatureVerificationConfig;
+
+/// Either a single repository, or several ones whose definitions are merged.
+#[derive(Deserialize, Serialize, Clone, PartialEq, JsonSchema)]
+#[serde(untagged)]
+pub enum GitSources {
+    Single(Box<GitConfig>),
+    /// Each repository is cloned into its own `repository_local_dir`.
+    Multiple(Vec<GitConfig>),
+}
+
+impl GitSources {
+    pub fn configs(&self) -> Vec<GitConfig> {
+        match self {
+            GitSources::Single(git_config) => vec![git_config.as_ref().clone()],
+            GitSources::Multiple(git_configs) => git_configs.clone(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, PartialEq, JsonSchema)]
+pub struct GitConfig {
+    /// URL of the repository containing the definitions.
+    #[schemars(length(min = 1))]
+    pub repository_url: String,
+    /// Mirrors of `repository_url`, tried in order whenever the repository cannot be reached.
+    /// Every mirror must resolve a version to the same commit.
+    #[serde(default)]
+    pub mirror_urls: Vec<String>,
+    /// Directory the repository is cloned into. Must be the directory the definitions are read from.
+    #[schemars(length(min = 1))]
+    pub repository_local_dir: String,
+
+    /// Name of the remote to pull from.
+    #[schemars(length(min = 1))]
+    pub remote_name: String,
+    /// Branch of the remote to follow.
+    #[schemars(length(min = 1))]
+    pub remote_branch: String,
+    /// What to do once `remote_branch` no longer descends from the commit checked out, such as
+    /// after a force-push.
+    #[serde(default)]
+    pub history_rewrite: HistoryRewritePolicy,
+
+    /// Username used to authenticate against the repository's host.
+    #[serde(default)]
+    pub username: Option<String>,
+    /// File containing the password or token used to authenticate against the repository's host.
+    #[serde(default)]
+    pub password_file: Option<String>,
+    /// Private key used to authenticate against the host of an SSH repository URL.
+    #[serde(default)]
+    pub ssh_key_file: Option<String>,
+    /// File containing the passphrase of `ssh_key_file`, if it is encrypted.
+    #[serde(default)]
+    pub ssh_passphrase_file: Option<String>,
+    /// File in the OpenSSH `known_hosts` format listing the keys the SSH host may present. Any
+    /// host key is accepted if not set.
+    #[serde(default)]
+    pub known_hosts_file: Option<String>,
+
+    /// Revision to check out instead of following `remote_branch`.
+    #[serde(default)]
+    pub pin: Option<GitPin>,
+    /// Signatures the checked out revisions must carry. Disabled if not set.
+    #[serde(default)]
+    pub signature_verification: Option<SignatureVerificationConfig>,
+}
+
+/// Revision the definitions are pinned to.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum GitPin {
+    /// Exact tag, such as `v1.4.2`.
+    Tag(String),
+    /// Exact commit hash, which may be abbreviated.
+    Commit(String),
+    /// Latest tag matching a semver requirement, such as `^1.4`. A leading `v` of tags is ignored.
+    Version(String),
+}
+
+/// Reaction to the followed branch being rewritten upstream.
+#[derive(Debug, Default, Deserialize, Serialize, Clone, Copy, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryRewritePolicy {
+    /// Keeps the definitions checked out and reports the downloader as degraded until the branch
+    /// descends from them again.
+    #[default]
+    Refuse,
+    /// Clones the repository again and follows the rewritten branch.
+    Reclone,
+}
+
+impl GitConfig {
+    /// `repository_url` followed by its mirrors.
+    pub fn repository_urls(&self) -> Vec<String> {
+        let mut repository_urls = vec![self.repository_url.clone()];
+        repository_urls.extend(self.mirror_urls.iter().cloned());
+
+        repository_urls
+    }
+
+    /// Whether the repository or any of its mirrors is reached through SSH.
+    pub fn uses_ssh(&self) -> bool {
+        self.repository_urls()
+            .iter()
+            .any(|repository_url| is_ssh_url(repository_url))
+    }
+
+    /// Reads the password from `password_file`, if any.
+    pub fn password(&self) -> Result<Option<String>, Error> {
+        match &self.password_file {
+            Some(password_file) => {
+                let password = secret::read_file(password_file.as_str())?;
+                secret::register(password.as_str());
+
+                Ok(Some(password))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Reads the passphrase of the SSH key from `ssh_passphrase_file`, if any.
+    pub fn ssh_passphrase(&self) -> Result<Option<String>, Error> {
+        match &self.ssh_passphrase_file {
+            Some(ssh_passphrase_file) => {
+                let ssh_passphrase = secret::read_file(ssh_passphrase_file.as_str())?;
+                secret::register(ssh_passphrase.as_str());
+
+                Ok(Some(ssh_passphrase))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+/// Whether `repository_url` is reached through SSH, either as `ssh://` URL or with the scp-like
+/// syntax, such as `git@host:repository`.
+pub fn is_ssh_url(repository_url: &str) -> bool {
+    if let Some((scheme, _)) = repository_url.split_once("://") {
+        return scheme == "ssh" || scheme == "git+ssh";
+    }
+
+    match repository_url.split_once(':') {
+        Some((host, _)) => !host.contains('/'),
+        None => false,
+    }
+}
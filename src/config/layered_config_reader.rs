@@ -0,0 +1,39 @@
+use std::io::Error;
+
+use crate::config::{
+    config::Config, config_layer::ConfigLayer, config_reader::ConfigReader, config_value,
+};
+
+/// Reads the configuration from a base reader and applies each layer on top of it, in order, so
+/// that the layers may provide keys the base reader lacks.
+pub struct LayeredConfigReader {
+    reader: Box<dyn ConfigReader>,
+    layers: Vec<Box<dyn ConfigLayer>>,
+}
+
+impl LayeredConfigReader {
+    pub fn new(
+        reader: Box<dyn ConfigReader>,
+        layers: Vec<Box<dyn ConfigLayer>>,
+    ) -> LayeredConfigReader {
+        LayeredConfigReader { reader, layers }
+    }
+}
+
+impl ConfigReader for LayeredConfigReader {
+    fn read(&self) -> Result<Config, Error> {
+        let base = self.reader.read_value()?;
+        let mut value = base.clone();
+
+        for layer in &self.layers {
+            layer.apply(&mut value)?;
+        }
+
+        // Reading the base again reports its errors at their position within the file.
+        if value == base {
+            return self.reader.read();
+        }
+
+        config_value::from_value(value)
+    }
+}
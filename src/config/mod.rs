@@ -1,6 +1,24 @@
+pub mod adaptive_polling_config;
+#[allow(clippy::module_inception)]
 pub mod config;
 pub mod config_file_reader;
+pub mod config_format;
+pub mod config_layer;
+pub mod config_override;
 pub mod config_reader;
 pub mod config_reader_builder;
+pub mod config_schema;
+pub mod config_validator;
+pub mod config_value;
+pub mod config_watcher;
 pub mod definition_downloader_config;
+pub mod degraded_mode_config;
+pub mod env_config_reader;
+pub mod freeze_window_config;
+pub mod git_config;
+pub mod layered_config_reader;
 pub mod output_config;
+pub mod retry_policy_config;
+pub mod signature_verification_config;
+pub mod source_config;
+pub mod status_config;
@@ -1,12 +1,51 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use super::retry_policy_config::{self, RetryPolicyConfig};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct OutputConfig {
+    /// Name of the AMQP queue the definitions are published to.
+    #[schemars(length(min = 1))]
     pub amqp_channel_name: String,
 
-    pub connection_retry_count: i32,
-    pub connection_retry_interval_seconds: u64,
+    /// Retries of connecting to the AMQP server.
+    #[serde(default)]
+    pub connection_retry: RetryPolicyConfig,
+    /// Retries of publishing a definition.
+    #[serde(default)]
+    pub set_retry: RetryPolicyConfig,
+
+    /// Deprecated, use `connection_retry` instead.
+    #[serde(default, skip_serializing)]
+    pub connection_retry_count: Option<u32>,
+    /// Deprecated, use `connection_retry` instead.
+    #[serde(default, skip_serializing)]
+    pub connection_retry_interval_seconds: Option<u64>,
+    /// Deprecated, use `set_retry` instead.
+    #[serde(default, skip_serializing)]
+    pub set_retry_count: Option<u32>,
+    /// Deprecated, use `set_retry` instead.
+    #[serde(default, skip_serializing)]
+    pub set_retry_interval_seconds: Option<u64>,
+}
 
-    pub set_retry_count: i32,
-    pub set_retry_interval_seconds: u64,
+impl OutputConfig {
+    /// Turns the flat retry keys replaced by the retry policies into the policies.
+    pub fn convert_legacy_keys(&mut self) {
+        retry_policy_config::convert_legacy_keys(
+            "output",
+            "connection_retry",
+            &mut self.connection_retry,
+            self.connection_retry_count.take(),
+            self.connection_retry_interval_seconds.take(),
+        );
+        retry_policy_config::convert_legacy_keys(
+            "output",
+            "set_retry",
+            &mut self.set_retry,
+            self.set_retry_count.take(),
+            self.set_retry_interval_seconds.take(),
+        );
+    }
 }
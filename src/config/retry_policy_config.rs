@@ -0,0 +1,164 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Exponential backoff used to retry a failing operation.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, JsonSchema)]
+pub struct RetryPolicyConfig {
+    /// Times the operation is retried after its first failure.
+    pub max_retries: u32,
+    /// Seconds to wait before the first retry.
+    pub initial_interval_seconds: u64,
+    /// Upper bound of the seconds to wait between two attempts.
+    pub max_interval_seconds: u64,
+    /// Factor the interval is multiplied by after each retry.
+    #[serde(default = "default_multiplier")]
+    #[schemars(range(min = 1))]
+    pub multiplier: f64,
+    /// Fraction of the interval which is randomly added or removed, so that several providers
+    /// do not retry in lockstep.
+    #[serde(default = "default_jitter")]
+    #[schemars(range(min = 0, max = 1))]
+    pub jitter: f64,
+    /// Seconds after the first failure from which no more retries are made.
+    #[serde(default)]
+    #[schemars(range(min = 1))]
+    pub deadline_seconds: Option<u64>,
+
+    /// Whether the policy is set within the config, rather than defaulted.
+    #[serde(skip, default = "is_set")]
+    pub is_set: bool,
+}
+
+/// Policy of a section which sets neither it nor the flat keys it replaced.
+impl Default for RetryPolicyConfig {
+    fn default() -> Self {
+        RetryPolicyConfig {
+            max_retries: 5,
+            initial_interval_seconds: 30,
+            max_interval_seconds: 900,
+            multiplier: default_multiplier(),
+            jitter: default_jitter(),
+            deadline_seconds: None,
+            is_set: false,
+        }
+    }
+}
+
+fn is_set() -> bool {
+    true
+}
+
+fn default_multiplier() -> f64 {
+    2.0
+}
+
+fn default_jitter() -> f64 {
+    0.1
+}
+
+/// Converts the flat `<name>_count` and `<name>_interval_seconds` keys of `section`, which the
+/// `<name>` policy replaced, into a fixed-interval policy so that older configs keep working.
+///
+/// The keys are ignored if the policy is set as well.
+pub fn convert_legacy_keys(
+    section: &str,
+    name: &str,
+    policy: &mut RetryPolicyConfig,
+    count: Option<u32>,
+    interval_seconds: Option<u64>,
+) {
+    if count.is_none() && interval_seconds.is_none() {
+        return;
+    }
+
+    if policy.is_set {
+        log::warn!(
+            "config keys '{section}.{name}_count' and '{section}.{name}_interval_seconds' are \
+             deprecated and ignored in favour of '{section}.{name}'"
+        );
+        return;
+    }
+
+    log::warn!(
+        "config keys '{section}.{name}_count' and '{section}.{name}_interval_seconds' are \
+         deprecated, use '{section}.{name}' instead"
+    );
+
+    let interval_seconds = interval_seconds.unwrap_or(policy.initial_interval_seconds);
+
+    *policy = RetryPolicyConfig {
+        max_retries: count.unwrap_or(policy.max_retries),
+        initial_interval_seconds: interval_seconds,
+        max_interval_seconds: interval_seconds,
+        multiplier: 1.0,
+        jitter: 0.0,
+        deadline_seconds: None,
+        is_set: true,
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn converted(
+        mut policy: RetryPolicyConfig,
+        count: Option<u32>,
+        interval_seconds: Option<u64>,
+    ) -> RetryPolicyConfig {
+        convert_legacy_keys("output", "set_retry", &mut policy, count, interval_seconds);
+        policy
+    }
+
+    #[test]
+    fn converts_legacy_keys_into_fixed_interval_policy() {
+        assert_eq!(
+            converted(RetryPolicyConfig::default(), Some(3), Some(10)),
+            RetryPolicyConfig {
+                max_retries: 3,
+                initial_interval_seconds: 10,
+                max_interval_seconds: 10,
+                multiplier: 1.0,
+                jitter: 0.0,
+                deadline_seconds: None,
+                is_set: true,
+            }
+        );
+    }
+
+    #[test]
+    fn completes_single_legacy_key_with_defaults() {
+        let policy = converted(RetryPolicyConfig::default(), Some(3), None);
+
+        assert_eq!(policy.max_retries, 3);
+        assert_eq!(
+            policy.initial_interval_seconds,
+            RetryPolicyConfig::default().initial_interval_seconds
+        );
+        assert_eq!(policy.max_interval_seconds, policy.initial_interval_seconds);
+
+        let policy = converted(RetryPolicyConfig::default(), None, Some(7));
+
+        assert_eq!(policy.max_retries, RetryPolicyConfig::default().max_retries);
+        assert_eq!(policy.initial_interval_seconds, 7);
+    }
+
+    #[test]
+    fn keeps_policy_set_alongside_legacy_keys() {
+        let policy = RetryPolicyConfig {
+            max_retries: 1,
+            is_set: true,
+            ..RetryPolicyConfig::default()
+        };
+
+        assert_eq!(converted(policy.clone(), Some(3), Some(10)), policy);
+    }
+
+    #[test]
+    fn keeps_policy_without_legacy_keys() {
+        assert_eq!(
+            converted(RetryPolicyConfig::default(), None, None),
+            RetryPolicyConfig::default()
+        );
+    }
+}
@@ -0,0 +1,21 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SignatureFormat {
+    /// Verified with `gpgv`.
+    Gpg,
+    /// Verified with `ssh-keygen -Y verify`.
+    Ssh,
+}
+
+/// Requires the checked out revisions to be signed by a trusted key.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, JsonSchema)]
+pub struct SignatureVerificationConfig {
+    pub format: SignatureFormat,
+    /// Trusted keys: a binary keyring exported with `gpg --export` for `gpg`, or an allowed signers
+    /// file for `ssh`.
+    #[schemars(length(min = 1))]
+    pub keyring: String,
+}
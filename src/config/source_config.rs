@@ -0,0 +1,35 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Where the definitions come from.
+#[derive(Debug, Default, Deserialize, Serialize, Clone, PartialEq, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SourceConfig {
+    /// Git repository configured within the `git` section.
+    #[default]
+    Git,
+    /// Existing directory which is read as-is, without cloning anything.
+    LocalDirectory(LocalDirectorySourceConfig),
+    /// `.tar.gz` or `.zip` release archive, unpacked into a staging directory.
+    Archive(ArchiveSourceConfig),
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, JsonSchema)]
+pub struct LocalDirectorySourceConfig {
+    /// Directory containing the definitions.
+    #[schemars(length(min = 1))]
+    pub path: String,
+    /// Version of the definitions. Read from the `VERSION` file within `path` if not set.
+    #[serde(default)]
+    pub version: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, JsonSchema)]
+pub struct ArchiveSourceConfig {
+    /// Archive to unpack, or drop directory whose most recently modified archive is unpacked.
+    #[schemars(length(min = 1))]
+    pub path: String,
+    /// Directory the archive is unpacked into, which the definitions are read from.
+    #[schemars(length(min = 1))]
+    pub staging_dir: String,
+}
@@ -0,0 +1,10 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Default, Deserialize, Serialize, Clone, PartialEq, JsonSchema)]
+pub struct StatusConfig {
+    /// Address the status endpoint listens on, such as `127.0.0.1:8080`. A port alone, such as
+    /// `8080`, listens on the loopback interface only. Disabled if not set.
+    #[serde(default)]
+    pub listen_address: Option<String>,
+}
@@ -0,0 +1,340 @@
+use std::{
+    fs::File,
+    path::{Path, PathBuf},
+    sync::{Mutex, MutexGuard},
+    time::SystemTime,
+};
+
+use flate2::read::GzDecoder;
+use serde::Deserialize;
+use zip::ZipArchive;
+
+use crate::{
+    config::source_config::ArchiveSourceConfig,
+    error::{Error, ErrorKind},
+};
+
+use super::{
+    definition_source::DefinitionSource,
+    directory_replacement::{remove_dir, replace_dir, sibling},
+};
+
+/// File at the root of every archive describing its content. It is not a `.json` file, as every
+/// one of those is read as a category.
+const MANIFEST_FILE_NAME: &str = "manifest.toml";
+
+/// Suffixes of the directories next to the staging directory an archive is unpacked into, and its
+/// previous content is moved to while being replaced.
+const UNPACKING_SUFFIX: &str = ".unpacking";
+const PREVIOUS_SUFFIX: &str = ".previous";
+
+#[derive(Deserialize)]
+struct ArchiveManifest {
+    version: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ArchiveFormat {
+    TarGz,
+    Zip,
+}
+
+impl ArchiveFormat {
+    fn from_path(path: &Path) -> Option<ArchiveFormat> {
+        let file_name = path.file_name()?.to_str()?.to_lowercase();
+
+        if file_name.ends_with(".tar.gz") || file_name.ends_with(".tgz") {
+            Some(ArchiveFormat::TarGz)
+        } else if file_name.ends_with(".zip") {
+            Some(ArchiveFormat::Zip)
+        } else {
+            None
+        }
+    }
+}
+
+/// Archive as found on disk, which is considered changed whenever any of its fields changes.
+#[derive(Debug, Clone, PartialEq)]
+struct ArchiveIdentity {
+    path: PathBuf,
+    format: ArchiveFormat,
+    modified: Option<SystemTime>,
+    size: u64,
+}
+
+/// Unpacks release archives into a staging directory, for sites without access to git.
+///
+/// The version of the definitions is taken from the `manifest.toml` at the root of the archive.
+pub struct ArchiveSource {
+    path: PathBuf,
+    staging_dir: PathBuf,
+    unpacked: Mutex<Option<ArchiveIdentity>>,
+}
+
+impl ArchiveSource {
+    pub fn new(config: &ArchiveSourceConfig) -> ArchiveSource {
+        ArchiveSource {
+            path: PathBuf::from(config.path.as_str()),
+            staging_dir: PathBuf::from(config.staging_dir.as_str()),
+            unpacked: Mutex::new(None),
+        }
+    }
+
+    fn unpacked(&self) -> MutexGuard<'_, Option<ArchiveIdentity>> {
+        match self.unpacked.lock() {
+            Ok(unpacked) => unpacked,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    /// Configured archive, or the most recently modified archive of the drop directory.
+    fn find_archive(&self) -> Result<ArchiveIdentity, Error> {
+        if !self.path.is_dir() {
+            return identify(self.path.as_path());
+        }
+
+        let entries = match std::fs::read_dir(&self.path) {
+            Ok(entries) => entries,
+            Err(error) => {
+                return Err(Error::new(
+                    ErrorKind::DefinitionsNotAvailable,
+                    format!(
+                        "failed to read archive drop directory '{}': {}",
+                        self.path.display(),
+                        error
+                    )
+                    .as_str(),
+                ))
+            }
+        };
+
+        let mut latest: Option<ArchiveIdentity> = None;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if !path.is_file() || ArchiveFormat::from_path(path.as_path()).is_none() {
+                continue;
+            }
+
+            let archive = identify(path.as_path())?;
+            let newer = match &latest {
+                Some(latest) => archive.modified > latest.modified,
+                None => true,
+            };
+
+            if newer {
+                latest = Some(archive);
+            }
+        }
+
+        match latest {
+            Some(archive) => Ok(archive),
+            None => Err(Error::new(
+                ErrorKind::DefinitionsNotAvailable,
+                format!(
+                    "no .tar.gz or .zip archive found in '{}'",
+                    self.path.display()
+                )
+                .as_str(),
+            )),
+        }
+    }
+
+    /// Replaces the content of the staging directory with the content of `archive`.
+    ///
+    /// The archive is unpacked next to the staging directory, which is only replaced once the
+    /// archive is fully unpacked, so that a failed unpack leaves the previous content in place.
+    fn unpack(&self, archive: &ArchiveIdentity) -> Result<(), Error> {
+        log::info!("unpacking archive '{}'", archive.path.display());
+
+        let unpacking_dir = sibling(self.staging_dir.as_path(), UNPACKING_SUFFIX)
+            .ok_or_else(|| unpack_error(archive, "the staging directory has no name"))?;
+        let previous_dir = sibling(self.staging_dir.as_path(), PREVIOUS_SUFFIX)
+            .ok_or_else(|| unpack_error(archive, "the staging directory has no name"))?;
+
+        if let Err(error) = remove_dir(unpacking_dir.as_path())
+            .and_then(|_| std::fs::create_dir_all(&unpacking_dir))
+        {
+            return Err(unpack_error(archive, error));
+        }
+
+        if let Err(error) = extract(archive, unpacking_dir.as_path()) {
+            if let Err(error) = remove_dir(unpacking_dir.as_path()) {
+                log::warn!(
+                    "failed to remove partially unpacked '{}': {}",
+                    unpacking_dir.display(),
+                    error
+                );
+            }
+
+            return Err(unpack_error(archive, error));
+        }
+
+        if let Err(error) = replace_dir(
+            &self.staging_dir,
+            unpacking_dir.as_path(),
+            previous_dir.as_path(),
+        ) {
+            return Err(unpack_error(archive, error));
+        }
+
+        *self.unpacked() = Some(archive.clone());
+        Ok(())
+    }
+}
+
+impl DefinitionSource for ArchiveSource {
+    fn fetch(&self) -> Result<(), Error> {
+        let archive = self.find_archive()?;
+        self.unpack(&archive)
+    }
+
+    /// Keeps the archive unpacked by a previous run, as long as its manifest is still readable.
+    /// It is unpacked again once an archive is found.
+    fn restore(&self) -> Result<(), Error> {
+        self.revision().map(|_| ())
+    }
+
+    /// Unpacks the archive again only if it changed, or if a newer one has been dropped.
+    fn refresh(&self) -> Result<(), Error> {
+        let archive = self.find_archive()?;
+
+        if self.unpacked().as_ref() == Some(&archive) {
+            return Ok(());
+        }
+
+        self.unpack(&archive)
+    }
+
+    /// A change of the drop directory is a new archive to unpack.
+    fn reload(&self) -> Result<(), Error> {
+        self.refresh()
+    }
+
+    fn has_changes(&self) -> Result<bool, Error> {
+        let archive = self.find_archive()?;
+
+        Ok(self.unpacked().as_ref() != Some(&archive))
+    }
+
+    fn revision(&self) -> Result<String, Error> {
+        let manifest_path = self.staging_dir.join(MANIFEST_FILE_NAME);
+
+        let content = match std::fs::read_to_string(&manifest_path) {
+            Ok(content) => content,
+            Err(error) => {
+                return Err(Error::new(
+                    ErrorKind::VersionReadFailure,
+                    format!(
+                        "failed to read archive manifest '{}': {}",
+                        manifest_path.display(),
+                        error
+                    )
+                    .as_str(),
+                ))
+            }
+        };
+
+        match toml::from_str::<ArchiveManifest>(content.as_str()) {
+            Ok(manifest) if !manifest.version.trim().is_empty() => {
+                Ok(manifest.version.trim().to_string())
+            }
+            Ok(_) => Err(Error::new(
+                ErrorKind::VersionReadFailure,
+                format!(
+                    "archive manifest '{}' has an empty version",
+                    manifest_path.display()
+                )
+                .as_str(),
+            )),
+            Err(error) => Err(Error::new(
+                ErrorKind::VersionReadFailure,
+                format!(
+                    "invalid archive manifest '{}': {}",
+                    manifest_path.display(),
+                    error
+                )
+                .as_str(),
+            )),
+        }
+    }
+
+    fn directories(&self) -> Vec<String> {
+        vec![self.staging_dir.to_string_lossy().to_string()]
+    }
+
+    /// The drop directory, or the directory containing the configured archive.
+    fn watched_directories(&self) -> Vec<String> {
+        if self.path.is_dir() {
+            return vec![self.path.to_string_lossy().to_string()];
+        }
+
+        match self.path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => {
+                vec![parent.to_string_lossy().to_string()]
+            }
+            _ => vec![String::from(".")],
+        }
+    }
+}
+
+fn identify(path: &Path) -> Result<ArchiveIdentity, Error> {
+    let format = match ArchiveFormat::from_path(path) {
+        Some(format) => format,
+        None => {
+            return Err(Error::new(
+                ErrorKind::DefinitionsNotAvailable,
+                format!(
+                    "'{}' is neither a .tar.gz nor a .zip archive",
+                    path.display()
+                )
+                .as_str(),
+            ))
+        }
+    };
+
+    match std::fs::metadata(path) {
+        Ok(metadata) => Ok(ArchiveIdentity {
+            path: path.to_path_buf(),
+            format,
+            modified: metadata.modified().ok(),
+            size: metadata.len(),
+        }),
+        Err(error) => Err(Error::new(
+            ErrorKind::DefinitionsNotAvailable,
+            format!("archive '{}' is not available: {}", path.display(), error).as_str(),
+        )),
+    }
+}
+
+/// Unpacks `archive` into `destination`.
+fn extract(archive: &ArchiveIdentity, destination: &Path) -> Result<(), String> {
+    let file = match File::open(&archive.path) {
+        Ok(file) => file,
+        Err(error) => return Err(error.to_string()),
+    };
+
+    // Both unpackers refuse entries which would end up outside of the destination.
+    match archive.format {
+        ArchiveFormat::TarGz => tar::Archive::new(GzDecoder::new(file))
+            .unpack(destination)
+            .map_err(|error| error.to_string()),
+        ArchiveFormat::Zip => match ZipArchive::new(file) {
+            Ok(mut zip_archive) => zip_archive
+                .extract(destination)
+                .map_err(|error| error.to_string()),
+            Err(error) => Err(error.to_string()),
+        },
+    }
+}
+
+fn unpack_error(archive: &ArchiveIdentity, error: impl std::fmt::Display) -> Error {
+    Error::new(
+        ErrorKind::FailedToUnpackArchive,
+        format!(
+            "failed to unpack archive '{}': {}",
+            archive.path.display(),
+            error
+        )
+        .as_str(),
+    )
+}
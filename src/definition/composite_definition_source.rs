@@ -0,0 +1,115 @@
+use crate::error::Error;
+
+use super::definition_source::DefinitionSource;
+
+/// Separates the revisions of the sources within the composite revision.
+const REVISION_SEPARATOR: &str = ",";
+
+/// Several named sources whose definitions are merged.
+///
+/// Its revision lists the revision of every source, such as `core@4f2a...,team-a@9c1b...`, so it
+/// changes whenever any of them changes.
+pub struct CompositeDefinitionSource {
+    sources: Vec<(String, Box<dyn DefinitionSource>)>,
+}
+
+impl CompositeDefinitionSource {
+    pub fn new(sources: Vec<(String, Box<dyn DefinitionSource>)>) -> CompositeDefinitionSource {
+        CompositeDefinitionSource { sources }
+    }
+}
+
+impl DefinitionSource for CompositeDefinitionSource {
+    fn fetch(&self) -> Result<(), Error> {
+        for (_, source) in &self.sources {
+            source.fetch()?;
+        }
+
+        Ok(())
+    }
+
+    fn restore(&self) -> Result<(), Error> {
+        for (_, source) in &self.sources {
+            source.restore()?;
+        }
+
+        Ok(())
+    }
+
+    fn refresh(&self) -> Result<(), Error> {
+        for (_, source) in &self.sources {
+            source.refresh()?;
+        }
+
+        Ok(())
+    }
+
+    fn reload(&self) -> Result<(), Error> {
+        for (_, source) in &self.sources {
+            source.reload()?;
+        }
+
+        Ok(())
+    }
+
+    /// Checks every source, even once one has changes, so that each of them tells its
+    /// [`Self::available_revision`].
+    fn has_changes(&self) -> Result<bool, Error> {
+        let mut has_changes = false;
+
+        for (_, source) in &self.sources {
+            has_changes |= source.has_changes()?;
+        }
+
+        Ok(has_changes)
+    }
+
+    /// Lists the available revision of every source which tells one, as [`Self::revision`] does.
+    fn available_revision(&self) -> Option<String> {
+        let revisions: Vec<String> = self
+            .sources
+            .iter()
+            .filter_map(|(name, source)| {
+                source
+                    .available_revision()
+                    .map(|revision| format!("{}@{}", name, revision))
+            })
+            .collect();
+
+        if revisions.is_empty() {
+            return None;
+        }
+
+        Some(revisions.join(REVISION_SEPARATOR))
+    }
+
+    fn cancel(&self) {
+        for (_, source) in &self.sources {
+            source.cancel();
+        }
+    }
+
+    fn revision(&self) -> Result<String, Error> {
+        let mut revisions: Vec<String> = Vec::new();
+
+        for (name, source) in &self.sources {
+            revisions.push(format!("{}@{}", name, source.revision()?));
+        }
+
+        Ok(revisions.join(REVISION_SEPARATOR))
+    }
+
+    fn directories(&self) -> Vec<String> {
+        self.sources
+            .iter()
+            .flat_map(|(_, source)| source.directories())
+            .collect()
+    }
+
+    fn watched_directories(&self) -> Vec<String> {
+        self.sources
+            .iter()
+            .flat_map(|(_, source)| source.watched_directories())
+            .collect()
+    }
+}
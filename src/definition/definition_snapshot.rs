@@ -0,0 +1,205 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use crate::error::{Error, ErrorKind};
+
+/// Directory the snapshots of the definitions are published into, within the working directory.
+/// Each provider running from the working directory publishes into its own directory within it,
+/// named after its process id.
+pub const SNAPSHOTS_DIRECTORY_NAME: &str = ".definition-snapshots";
+
+/// Directory of the git metadata, which is not part of the definitions.
+const GIT_DIRECTORY_NAME: &str = ".git";
+
+/// Suffix of a snapshot which is still being copied.
+const STAGING_SUFFIX: &str = ".staging";
+
+static SNAPSHOT_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Consistent copy of the definitions of one revision, which the source cannot modify anymore.
+///
+/// A snapshot is copied into a staging directory first, then renamed into place, so that it is
+/// either complete or not there at all. It is removed once dropped.
+#[derive(Debug)]
+pub struct DefinitionSnapshot {
+    directory: PathBuf,
+    /// Source directories along with the directories they were copied into.
+    paths: Vec<(String, String)>,
+}
+
+impl DefinitionSnapshot {
+    /// Copies `directories`, leaving out their git metadata.
+    pub fn take(directories: &[String]) -> Result<DefinitionSnapshot, Error> {
+        let snapshots_path = instance_snapshots_path();
+        let name = SNAPSHOT_COUNTER.fetch_add(1, Ordering::Relaxed).to_string();
+
+        let directory = snapshots_path.join(name.as_str());
+        let staging_directory = snapshots_path.join(format!("{}{}", name, STAGING_SUFFIX));
+
+        let mut paths: Vec<(String, String)> = Vec::new();
+        for (index, source_directory) in directories.iter().enumerate() {
+            let result = copy_directory(
+                Path::new(source_directory),
+                staging_directory.join(index.to_string()).as_path(),
+            );
+
+            if let Err(error) = result {
+                let _ = std::fs::remove_dir_all(&staging_directory);
+
+                return Err(Error::new(
+                    ErrorKind::DataWritingFailure,
+                    format!(
+                        "failed to copy '{}' into a snapshot: {}",
+                        source_directory, error
+                    )
+                    .as_str(),
+                ));
+            }
+
+            paths.push((
+                source_directory.clone(),
+                directory
+                    .join(index.to_string())
+                    .to_string_lossy()
+                    .to_string(),
+            ));
+        }
+
+        if let Err(error) = std::fs::rename(&staging_directory, &directory) {
+            let _ = std::fs::remove_dir_all(&staging_directory);
+
+            return Err(Error::new(
+                ErrorKind::DataWritingFailure,
+                format!(
+                    "failed to publish snapshot '{}': {}",
+                    directory.display(),
+                    error
+                )
+                .as_str(),
+            ));
+        }
+
+        Ok(DefinitionSnapshot { directory, paths })
+    }
+
+    /// Source directories along with the directories they were copied into.
+    pub fn paths(&self) -> &[(String, String)] {
+        self.paths.as_slice()
+    }
+
+    /// Removes the snapshots left over by a previous run with the same process id, as well as
+    /// those of providers which are not running anymore, leaving the ones of other running
+    /// providers alone.
+    pub fn clear() -> Result<(), Error> {
+        let snapshots_path = instance_snapshots_path();
+
+        let result = remove_stale_snapshots(snapshots_path.as_path())
+            .and_then(|_| std::fs::create_dir_all(&snapshots_path));
+
+        match result {
+            Ok(_) => Ok(()),
+            Err(error) => Err(Error::new(
+                ErrorKind::DataWritingFailure,
+                format!(
+                    "failed to clear snapshots directory '{}': {}",
+                    snapshots_path.display(),
+                    error
+                )
+                .as_str(),
+            )),
+        }
+    }
+}
+
+impl Drop for DefinitionSnapshot {
+    fn drop(&mut self) {
+        if let Err(error) = std::fs::remove_dir_all(&self.directory) {
+            log::warn!(
+                "failed to remove snapshot '{}': {}",
+                self.directory.display(),
+                error
+            );
+        }
+    }
+}
+
+/// Directory the snapshots of this provider are published into.
+fn instance_snapshots_path() -> PathBuf {
+    Path::new(SNAPSHOTS_DIRECTORY_NAME).join(std::process::id().to_string())
+}
+
+fn remove_stale_snapshots(snapshots_path: &Path) -> std::io::Result<()> {
+    if snapshots_path.exists() {
+        std::fs::remove_dir_all(snapshots_path)?;
+    }
+
+    let entries = match std::fs::read_dir(SNAPSHOTS_DIRECTORY_NAME) {
+        Ok(entries) => entries,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(error) => return Err(error),
+    };
+
+    for entry in entries {
+        let entry = entry?;
+
+        let is_stale = match entry.file_name().to_string_lossy().parse::<u32>() {
+            Ok(process_id) => !is_running(process_id),
+            // Left over by a version publishing every snapshot straight into the directory.
+            Err(_) => true,
+        };
+
+        if is_stale {
+            if let Err(error) = std::fs::remove_dir_all(entry.path()) {
+                log::warn!(
+                    "failed to remove stale snapshots '{}': {}",
+                    entry.path().display(),
+                    error
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether a process with `process_id` is running. Assumed to be running where it cannot be told.
+fn is_running(process_id: u32) -> bool {
+    if cfg!(target_os = "linux") {
+        return Path::new("/proc").join(process_id.to_string()).exists();
+    }
+
+    true
+}
+
+/// Copies the files and directories within `from`, leaving out git metadata and the snapshots
+/// themselves. Symbolic links are skipped, as they may point outside of `from` or back into it.
+fn copy_directory(from: &Path, to: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(to)?;
+
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        let file_name = entry.file_name();
+
+        if file_name == GIT_DIRECTORY_NAME || file_name == SNAPSHOTS_DIRECTORY_NAME {
+            continue;
+        }
+
+        let destination = to.join(&file_name);
+        let file_type = entry.file_type()?;
+
+        if file_type.is_symlink() {
+            log::warn!(
+                "skipping symbolic link '{}' while taking a snapshot",
+                entry.path().display()
+            );
+        } else if file_type.is_dir() {
+            copy_directory(entry.path().as_path(), destination.as_path())?;
+        } else {
+            std::fs::copy(entry.path(), destination)?;
+        }
+    }
+
+    Ok(())
+}
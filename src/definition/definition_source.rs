@@ -0,0 +1,54 @@
+use crate::error::{Error, ErrorKind};
+
+/// Origin of the definitions, which makes them available within a local directory.
+///
+/// Every method may block, so callers are expected to run them outside of the async workers.
+pub trait DefinitionSource: Send + Sync {
+    /// Makes the definitions available for the first time.
+    fn fetch(&self) -> Result<(), Error>;
+
+    /// Makes the definitions a previous run left behind available again, without reaching the
+    /// origin of the source, so that the provider can start while the origin is unreachable.
+    fn restore(&self) -> Result<(), Error> {
+        Err(Error::new(
+            ErrorKind::DefinitionsNotAvailable,
+            "source cannot restore definitions left behind by a previous run",
+        ))
+    }
+
+    /// Brings the already fetched definitions up to date.
+    fn refresh(&self) -> Result<(), Error>;
+
+    /// Cheaply checks whether a refresh would change the definitions, so that it can be skipped.
+    fn has_changes(&self) -> Result<bool, Error> {
+        Ok(true)
+    }
+
+    /// Identifies what the last call to [`Self::has_changes`] found available, such as the commit
+    /// a refresh would move to, if the source can tell it without refreshing.
+    fn available_revision(&self) -> Option<String> {
+        None
+    }
+
+    /// Asks the operation currently running, if any, to stop as soon as possible, failing it.
+    fn cancel(&self) {}
+
+    /// Identifies the revision of the definitions currently available.
+    fn revision(&self) -> Result<String, Error>;
+
+    /// Directories the definitions are available in, which are copied into a snapshot for the
+    /// reader after every fetch or refresh.
+    fn directories(&self) -> Vec<String>;
+
+    /// Directories whose changes make the definitions be read again in the `watch` update mode.
+    fn watched_directories(&self) -> Vec<String> {
+        self.directories()
+    }
+
+    /// Picks up the changes noticed within the watched directories before the definitions are read
+    /// again, without reaching the origin of the source. Nothing needs to be done as long as the
+    /// watched directories are the directories of the definitions.
+    fn reload(&self) -> Result<(), Error> {
+        Ok(())
+    }
+}
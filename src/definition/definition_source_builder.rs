@@ -0,0 +1,45 @@
+use std::sync::Arc;
+
+use crate::{
+    config::{config::Config, git_config::GitSources, source_config::SourceConfig},
+    error::{Error, ErrorKind},
+};
+
+use super::{
+    archive_source::ArchiveSource, composite_definition_source::CompositeDefinitionSource,
+    definition_source::DefinitionSource, file_reader::CATEGORIES_PATH,
+    git_definition_source::GitDefinitionSource, local_directory_source::LocalDirectorySource,
+};
+
+/// Builds the source selected by the configuration.
+pub fn build(config: &Config) -> Result<Arc<dyn DefinitionSource>, Error> {
+    match config.source() {
+        SourceConfig::Git => match config.git() {
+            Some(GitSources::Single(git_config)) => Ok(Arc::new(GitDefinitionSource::new(
+                &git_config,
+                CATEGORIES_PATH,
+            )?)),
+            Some(GitSources::Multiple(git_configs)) => {
+                let mut sources: Vec<(String, Box<dyn DefinitionSource>)> = Vec::new();
+
+                // Each repository is read from the directory it is cloned into.
+                for git_config in git_configs {
+                    let local_dir = git_config.repository_local_dir.clone();
+                    let source = GitDefinitionSource::new(&git_config, local_dir.as_str())?;
+
+                    sources.push((local_dir, Box::new(source)));
+                }
+
+                Ok(Arc::new(CompositeDefinitionSource::new(sources)))
+            }
+            None => Err(Error::new(
+                ErrorKind::DefinitionsNotAvailable,
+                "git source selected, but no git config is set",
+            )),
+        },
+        SourceConfig::LocalDirectory(local_directory_config) => {
+            Ok(Arc::new(LocalDirectorySource::new(&local_directory_config)))
+        }
+        SourceConfig::Archive(archive_config) => Ok(Arc::new(ArchiveSource::new(&archive_config))),
+    }
+}
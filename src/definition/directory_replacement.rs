@@ -0,0 +1,46 @@
+use std::path::{Path, PathBuf};
+
+/// Hidden directory next to `dir`, named after it with `suffix` appended, such as the one a new
+/// content is prepared into before replacing `dir`.
+pub fn sibling(dir: &Path, suffix: &str) -> Option<PathBuf> {
+    let name = dir.file_name()?.to_string_lossy();
+
+    Some(dir.with_file_name(format!(".{}{}", name, suffix)))
+}
+
+/// Removes `dir` along with everything within it, if it exists.
+pub fn remove_dir(dir: &Path) -> Result<(), std::io::Error> {
+    match std::fs::remove_dir_all(dir) {
+        Err(error) if error.kind() != std::io::ErrorKind::NotFound => Err(error),
+        _ => Ok(()),
+    }
+}
+
+/// Moves `new_dir` in place of `dir`, if any, which is moved to `previous_dir` meanwhile and put
+/// back if the move fails.
+pub fn replace_dir(dir: &Path, new_dir: &Path, previous_dir: &Path) -> Result<(), std::io::Error> {
+    remove_dir(previous_dir)?;
+
+    let has_previous = dir.exists();
+    if has_previous {
+        std::fs::rename(dir, previous_dir)?;
+    }
+
+    if let Err(error) = std::fs::rename(new_dir, dir) {
+        if has_previous {
+            std::fs::rename(previous_dir, dir)?;
+        }
+
+        return Err(error);
+    }
+
+    if let Err(error) = remove_dir(previous_dir) {
+        log::warn!(
+            "failed to remove replaced '{}': {}",
+            previous_dir.display(),
+            error
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,93 @@
+use std::{
+    path::{Component, Path},
+    time::Duration,
+};
+
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use tokio::{sync::mpsc, time::sleep};
+
+use crate::{
+    definition::definition_snapshot::SNAPSHOTS_DIRECTORY_NAME,
+    error::{Error, ErrorKind},
+};
+
+/// Directory of the git metadata, whose changes do not affect the definitions.
+const GIT_DIRECTORY_NAME: &str = ".git";
+
+/// Notices changes of the files within directories and their subdirectories.
+pub struct DirectoryWatcher {
+    // Dropping the watcher stops the notifications.
+    _watcher: RecommendedWatcher,
+    event_receiver: mpsc::UnboundedReceiver<()>,
+}
+
+impl DirectoryWatcher {
+    pub fn new(directories: &[String]) -> Result<DirectoryWatcher, Error> {
+        let (event_sender, event_receiver) = mpsc::unbounded_channel::<()>();
+
+        let mut watcher = match RecommendedWatcher::new(
+            move |result: notify::Result<notify::Event>| {
+                if let Ok(event) = result {
+                    if !matches!(event.kind, EventKind::Access(_))
+                        && event.paths.iter().any(|path| !is_ignored(path))
+                    {
+                        let _ = event_sender.send(());
+                    }
+                }
+            },
+            notify::Config::default(),
+        ) {
+            Ok(watcher) => watcher,
+            Err(error) => {
+                return Err(Error::new(
+                    ErrorKind::WatchFailure,
+                    format!("failed to create directory watcher: {}", error).as_str(),
+                ))
+            }
+        };
+
+        for directory in directories {
+            if let Err(error) = watcher.watch(Path::new(directory), RecursiveMode::Recursive) {
+                return Err(Error::new(
+                    ErrorKind::WatchFailure,
+                    format!("failed to watch directory '{}': {}", directory, error).as_str(),
+                ));
+            }
+        }
+
+        Ok(DirectoryWatcher {
+            _watcher: watcher,
+            event_receiver,
+        })
+    }
+
+    /// Waits for a change, then for the following burst of changes to settle for `debounce`.
+    ///
+    /// Returns `false` if no further change can be noticed.
+    pub async fn changed(&mut self, debounce: Duration) -> bool {
+        if self.event_receiver.recv().await.is_none() {
+            return false;
+        }
+
+        loop {
+            sleep(debounce).await;
+
+            let mut settled = true;
+            while self.event_receiver.try_recv().is_ok() {
+                settled = false;
+            }
+
+            if settled {
+                return true;
+            }
+        }
+    }
+}
+
+/// Whether `path` is git metadata or a snapshot, neither of which are definitions being edited.
+fn is_ignored(path: &Path) -> bool {
+    path.components().any(|component| {
+        component == Component::Normal(GIT_DIRECTORY_NAME.as_ref())
+            || component == Component::Normal(SNAPSHOTS_DIRECTORY_NAME.as_ref())
+    })
+}
@@ -1,118 +1,624 @@
-use std::time::Duration;
+use std::{sync::Arc, time::Duration};
 
-use async_recursion::async_recursion;
-use cooplan_definition_git_downloader::downloader::Downloader;
-use tokio::{sync::watch::Sender, time::sleep};
+use chrono::{DateTime, Utc};
+use tokio::{
+    sync::watch::{Receiver, Sender},
+    task::{self, JoinHandle},
+    time::{sleep, timeout},
+};
 
 use crate::{
-    config::definition_downloader_config::DefinitionDownloaderConfig,
-    definition::downloader_state::DownloaderState,
+    config::{
+        definition_downloader_config::{DefinitionDownloaderConfig, UpdateMode},
+        degraded_mode_config::DegradedModeConfig,
+    },
+    definition::{
+        definition_snapshot::DefinitionSnapshot, definition_source::DefinitionSource,
+        directory_watcher::DirectoryWatcher, downloader_state::DownloaderState,
+    },
+    error::{Error, ErrorKind},
+    retry_policy::{degrade, RetryPolicy},
+    status::{
+        provider_status::PendingUpdate,
+        status_reporter::{Stage, StatusReporter},
+    },
+    update_schedule::UpdateSchedule,
 };
 
+/// Time a timed out source operation is given to stop once cancelled, before the next operation is
+/// given up instead of waiting for it.
+const CANCELLATION_GRACE_PERIOD: Duration = Duration::from_secs(30);
+
+/// Drives a definition source, running its blocking operations on tokio's blocking pool so that
+/// they never stall the async workers.
 pub struct DownloaderAsyncWrapper {
-    downloader: Downloader,
-    config: DefinitionDownloaderConfig,
+    source: Arc<dyn DefinitionSource>,
+    config_receiver: Receiver<DefinitionDownloaderConfig>,
+    degraded_mode_config: DegradedModeConfig,
     state_sender: Sender<DownloaderState>,
+    status_reporter: StatusReporter,
 
-    download_retry_count: i32,
-    update_retry_count: i32,
+    /// Source operation which timed out, but is still running on the blocking pool.
+    abandoned_operation: Option<JoinHandle<()>>,
+    /// Interval until the next update while polling adaptively.
+    adaptive_interval: Option<Duration>,
+    /// Changes found during a freeze window, which are only downloaded once it ends.
+    pending_update: Option<PendingUpdate>,
 }
 
 impl DownloaderAsyncWrapper {
     pub fn new(
-        definition_downloader: Downloader,
-        definition_downloader_config: DefinitionDownloaderConfig,
+        source: Arc<dyn DefinitionSource>,
+        definition_downloader_config_receiver: Receiver<DefinitionDownloaderConfig>,
+        degraded_mode_config: DegradedModeConfig,
         definition_downloader_state_sender: Sender<DownloaderState>,
+        status_reporter: StatusReporter,
     ) -> DownloaderAsyncWrapper {
         DownloaderAsyncWrapper {
-            downloader: definition_downloader,
-            config: definition_downloader_config,
+            source,
+            config_receiver: definition_downloader_config_receiver,
+            degraded_mode_config,
             state_sender: definition_downloader_state_sender,
-
-            download_retry_count: 0,
-            update_retry_count: 0,
+            status_reporter,
+            abandoned_operation: None,
+            adaptive_interval: None,
+            pending_update: None,
         }
     }
 
-    pub async fn run(&mut self) {
-        self.try_download().await;
+    /// Latest configuration, which may change while running.
+    fn config(&self) -> DefinitionDownloaderConfig {
+        self.config_receiver.borrow().clone()
+    }
+
+    /// Downloads the definitions and keeps them updated, either by polling the source or by
+    /// watching the path it reports as watched. Changes found during a freeze window are only
+    /// downloaded once it ends.
+    ///
+    /// Only returns if the download cannot be completed and the degraded mode is configured to exit.
+    pub async fn run(&mut self) -> Result<(), Error> {
+        self.try_download().await?;
 
-        let update_duration = Duration::from_secs(self.config.update_interval_seconds);
+        let mut directory_watcher: Option<DirectoryWatcher> = None;
+        let mut config_open = true;
 
         loop {
-            sleep(update_duration).await;
+            let config = self.config();
+            let update_schedule = self.update_schedule(&config);
+
+            let update_duration = match update_schedule.until_next_update(Utc::now()) {
+                Some(update_duration) => update_duration,
+                None => self.poll_interval(&config),
+            };
+            let release_delay = self.release_delay(&update_schedule);
+
+            match config.update_mode {
+                UpdateMode::Poll => directory_watcher = None,
+                UpdateMode::Watch => {
+                    if directory_watcher.is_none() {
+                        directory_watcher = self.watch_directory();
+                    }
+                }
+            }
+
+            let watching = directory_watcher.is_some();
+            let debounce = Duration::from_millis(config.watch_debounce_milliseconds);
+
+            tokio::select! {
+                _ = sleep(update_duration), if !watching => {
+                    let changed = match update_schedule.freeze_end(Utc::now()) {
+                        Some(freeze_end) => self.try_check(freeze_end).await,
+                        None => self.try_update().await,
+                    };
+                    self.adapt_poll_interval(changed);
+                }
+                changed = wait_for_change(&mut directory_watcher, debounce), if watching => {
+                    if !changed {
+                        log::warn!("stopped watching the definitions");
+                        directory_watcher = None;
+                    } else if let Some(freeze_end) = update_schedule.freeze_end(Utc::now()) {
+                        self.hold(None, freeze_end);
+                    } else {
+                        log::info!("watched definitions changed, reading them again");
+                        self.try_reread().await;
+                    }
+                }
+                _ = sleep(release_delay.unwrap_or_default()), if release_delay.is_some() => {
+                    log::info!("freeze window ended, applying the changes found during it");
+                    // Failing to apply them leaves it to the next update.
+                    self.discard_pending_update();
 
-            self.try_update().await;
+                    if watching {
+                        self.try_reread().await;
+                    } else {
+                        let changed = self.try_update().await;
+                        self.adapt_poll_interval(changed);
+                    }
+                }
+                result = self.config_receiver.changed(), if config_open => match result {
+                    Ok(_) => log::info!(
+                        "applying new definition downloader config, update mode: {:?}, update interval: {}s",
+                        self.config().update_mode,
+                        self.config().update_interval_seconds
+                    ),
+                    Err(_) => config_open = false,
+                },
+            }
         }
     }
 
-    #[async_recursion]
-    async fn try_download(&mut self) {
-        if self.state_sender.borrow().available {
-            self.state_sender.send_replace(DownloaderState::new(false));
+    /// Starts watching the path reported by the source, falling back to polling if it cannot be
+    /// watched.
+    fn watch_directory(&self) -> Option<DirectoryWatcher> {
+        let directories = self.source.watched_directories();
+
+        match DirectoryWatcher::new(directories.as_slice()) {
+            Ok(directory_watcher) => {
+                log::info!("watching definitions in {:?}", directories);
+                Some(directory_watcher)
+            }
+            Err(error) => {
+                log::error!("falling back to polling: {}", error);
+                None
+            }
         }
+    }
+
+    /// Makes the reader read the definitions again, without refreshing the source.
+    ///
+    /// Files may change without the revision moving, such as within a local directory, so the
+    /// definitions are read again regardless.
+    async fn try_reread(&mut self) {
+        let update_timeout = Duration::from_secs(self.config().update_timeout_seconds);
 
-        match self.downloader.download() {
-            Ok(_) => {
-                log::info!("successfully downloaded definitions");
-                self.download_retry_count = 0;
+        match self
+            .run_blocking("reload", update_timeout, |source| {
+                source.reload()?;
+                let revision = source.revision()?;
 
-                self.state_sender.send_replace(DownloaderState::new(true));
+                Ok((revision, DefinitionSnapshot::take(&source.directories())?))
+            })
+            .await
+        {
+            Ok((revision, snapshot)) => {
+                self.status_reporter.set_healthy(Stage::Downloader);
+                self.publish(revision, snapshot);
             }
             Err(error) => {
-                log::warn!("failed to download definitions: {}", error);
-                if self.download_retry_count >= self.config.download_retry_count {
-                    std::process::exit(1);
+                log::warn!("failed to read changed definitions again: {}", error);
+            }
+        }
+    }
+
+    async fn try_download(&mut self) -> Result<(), Error> {
+        if self.state_sender.borrow().available {
+            self.state_sender
+                .send_replace(DownloaderState::new_not_available());
+        }
+
+        // A restart during a freeze window keeps the definitions served before it, rather than
+        // moving to the latest ones.
+        if let Some(freeze_end) = self.update_schedule(&self.config()).freeze_end(Utc::now()) {
+            match self.restore().await {
+                Ok((revision, snapshot)) => {
+                    log::info!(
+                        "serving the definitions left behind at revision {} until the freeze window ends at {}",
+                        revision,
+                        freeze_end
+                    );
+                    self.status_reporter.set_healthy(Stage::Downloader);
+                    self.state_sender
+                        .send_replace(DownloaderState::new(true, revision, snapshot));
+
+                    return Ok(());
+                }
+                Err(error) => {
+                    log::info!(
+                        "downloading definitions despite the freeze window, as none are left behind: {}",
+                        error
+                    );
+                }
+            }
+        }
+
+        let mut retry = RetryPolicy::new(self.config().download_retry).start();
+        let mut restore_attempted = false;
+
+        loop {
+            let download_timeout = Duration::from_secs(self.config().download_timeout_seconds);
+
+            match self
+                .run_blocking("fetch", download_timeout, |source| {
+                    source.fetch()?;
+                    let revision = source.revision()?;
+
+                    Ok((revision, DefinitionSnapshot::take(&source.directories())?))
+                })
+                .await
+            {
+                Ok((revision, snapshot)) => {
+                    log::info!("successfully downloaded definitions");
+                    self.status_reporter.set_healthy(Stage::Downloader);
+                    self.state_sender
+                        .send_replace(DownloaderState::new(true, revision, snapshot));
+
+                    return Ok(());
                 }
+                Err(error) => {
+                    log::warn!("failed to download definitions: {}", error);
 
-                sleep(Duration::from_secs(
-                    self.config.download_retry_interval_seconds,
-                ))
-                .await;
+                    if self.config().offline_start && !restore_attempted {
+                        restore_attempted = true;
+
+                        if self.try_restore(&error).await {
+                            return Ok(());
+                        }
+                    }
+
+                    let delay = match retry.next_delay() {
+                        Some(delay) => {
+                            log::warn!(
+                                "retrying to download definitions in {:?}, count: {}",
+                                delay,
+                                retry.retries()
+                            );
+                            delay
+                        }
+                        None => {
+                            let message = format!("failed to download definitions: {}", error);
+                            degrade(
+                                &self.degraded_mode_config,
+                                &self.status_reporter,
+                                Stage::Downloader,
+                                message.as_str(),
+                            )?
+                        }
+                    };
+
+                    sleep(delay).await;
+                }
+            }
+        }
+    }
 
-                self.download_retry_count += 1;
+    /// Publishes the definitions a previous run left behind, leaving it to the updates to reach the
+    /// origin of the source later on.
+    ///
+    /// Returns whether the definitions could be restored.
+    async fn try_restore(&mut self, download_error: &Error) -> bool {
+        match self.restore().await {
+            Ok((revision, snapshot)) => {
                 log::warn!(
-                    "retrying to download definitions, count: {}",
-                    self.download_retry_count
+                    "starting offline with the definitions left behind at revision {}",
+                    revision
                 );
+                // Stays degraded until an update reaches the origin.
+                self.status_reporter.set_degraded(
+                    Stage::Downloader,
+                    format!("started offline: {}", download_error).as_str(),
+                );
+                self.state_sender
+                    .send_replace(DownloaderState::new(true, revision, snapshot));
 
-                self.try_download().await;
+                true
+            }
+            Err(error) => {
+                log::warn!("cannot start offline: {}", error);
+                false
             }
         }
     }
 
-    #[async_recursion]
-    async fn try_update(&mut self) {
-        if self.state_sender.borrow().available {
-            self.state_sender.send_replace(DownloaderState::new(false));
-        }
+    /// Makes the definitions a previous run left behind available again, along with their revision.
+    async fn restore(&mut self) -> Result<(String, DefinitionSnapshot), Error> {
+        let restore_timeout = Duration::from_secs(self.config().download_timeout_seconds);
+
+        self.run_blocking("restore", restore_timeout, |source| {
+            source.restore()?;
+            let revision = source.revision()?;
+
+            Ok((revision, DefinitionSnapshot::take(&source.directories())?))
+        })
+        .await
+    }
 
-        match self.downloader.update() {
-            Ok(_) => {
-                log::info!("sucessfully updated definitions");
-                self.update_retry_count = 0;
+    /// Checks whether the source has changes during a freeze window, without refreshing it, so
+    /// that they are downloaded once the window ends at `freeze_end`.
+    ///
+    /// Returns whether changes were found.
+    async fn try_check(&mut self, freeze_end: DateTime<Utc>) -> bool {
+        let update_timeout = Duration::from_secs(self.config().update_timeout_seconds);
 
-                self.state_sender.send_replace(DownloaderState::new(true));
+        match self
+            .run_blocking("check for changes", update_timeout, |source| {
+                if !source.has_changes()? {
+                    return Ok(None);
+                }
+
+                Ok(Some(source.available_revision()))
+            })
+            .await
+        {
+            Ok(Some(revision)) => {
+                self.status_reporter.set_healthy(Stage::Downloader);
+                self.hold(revision, freeze_end);
+
+                true
+            }
+            Ok(None) => {
+                self.status_reporter.set_healthy(Stage::Downloader);
+                log::debug!("definitions are up to date");
+
+                false
             }
             Err(error) => {
-                log::warn!("failed to update definitions: {}", error);
-                if self.update_retry_count >= self.config.update_retry_count {
-                    return;
+                // Checked again on the next update.
+                log::warn!(
+                    "failed to check for changes during the freeze window: {}",
+                    error
+                );
+
+                false
+            }
+        }
+    }
+
+    /// Refreshes the source if it has changes, making the reader read the definitions again only
+    /// if their revision moved.
+    ///
+    /// Returns whether the revision moved.
+    async fn try_update(&mut self) -> bool {
+        let mut retry = RetryPolicy::new(self.config().update_retry).start();
+
+        loop {
+            let update_timeout = Duration::from_secs(self.config().update_timeout_seconds);
+            let previous_revision = self.state_sender.borrow().revision.clone();
+
+            match self
+                .run_blocking("refresh", update_timeout, move |source| {
+                    if !source.has_changes()? {
+                        return Ok(None);
+                    }
+
+                    source.refresh()?;
+                    let revision = source.revision()?;
+
+                    if previous_revision.as_ref() == Some(&revision) {
+                        return Ok(None);
+                    }
+
+                    // The reader keeps reading the previous snapshot until this one is complete.
+                    Ok(Some((
+                        revision,
+                        DefinitionSnapshot::take(&source.directories())?,
+                    )))
+                })
+                .await
+            {
+                Ok(update) => {
+                    self.status_reporter.set_healthy(Stage::Downloader);
+
+                    let (revision, snapshot) = match update {
+                        Some(update) => update,
+                        None => {
+                            log::debug!("definitions are up to date");
+                            self.discard_pending_update();
+                            return false;
+                        }
+                    };
+
+                    self.publish(revision, snapshot);
+                    return true;
                 }
+                Err(error) => {
+                    log::warn!("failed to update definitions: {}", error);
 
-                sleep(Duration::from_secs(
-                    self.config.update_retry_interval_seconds,
-                ))
-                .await;
+                    let delay = match error.kind() {
+                        // Retrying cannot make a refused rewrite of the history acceptable.
+                        ErrorKind::HistoryRewritten => None,
+                        _ => retry.next_delay(),
+                    };
 
-                self.update_retry_count += 1;
-                log::warn!(
-                    "retrying to update definitions, count: {}",
-                    self.update_retry_count
+                    match delay {
+                        Some(delay) => {
+                            log::warn!(
+                                "retrying to update definitions in {:?}, count: {}",
+                                delay,
+                                retry.retries()
+                            );
+                            sleep(delay).await;
+                        }
+                        None => {
+                            // The last published definition stays in place and the update is retried
+                            // on the next interval.
+                            log::error!(
+                                "giving up updating definitions until the next update: {}",
+                                error
+                            );
+                            self.status_reporter.set_degraded(
+                                Stage::Downloader,
+                                format!("failed to update definitions: {}", error).as_str(),
+                            );
+
+                            return false;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Makes the reader read `snapshot`, which supersedes the changes found during the last freeze
+    /// window.
+    fn publish(&mut self, revision: String, snapshot: DefinitionSnapshot) {
+        log::info!("sucessfully updated definitions to revision {}", revision);
+        self.discard_pending_update();
+        self.state_sender
+            .send_replace(DownloaderState::new(true, revision, snapshot));
+    }
+
+    /// Schedule of the updates, which polls every interval and never freezes if it is invalid.
+    fn update_schedule(&self, config: &DefinitionDownloaderConfig) -> UpdateSchedule {
+        match UpdateSchedule::new(config) {
+            Ok(update_schedule) => update_schedule,
+            Err(error) => {
+                log::error!("ignoring update schedule: {}", error);
+                UpdateSchedule::default()
+            }
+        }
+    }
+
+    /// Records changes found during the freeze window ending at `freeze_end`, leading to
+    /// `revision` if known, so that they are applied once it ends.
+    fn hold(&mut self, revision: Option<String>, freeze_end: DateTime<Utc>) {
+        let pending_update = PendingUpdate {
+            revision,
+            held_until: u64::try_from(freeze_end.timestamp()).unwrap_or(0),
+        };
+
+        if self.pending_update.as_ref() == Some(&pending_update) {
+            return;
+        }
+
+        log::info!(
+            "found changes up to revision {}, holding them back until the freeze window ends at {}",
+            pending_update.revision.as_deref().unwrap_or("unknown"),
+            freeze_end
+        );
+
+        self.status_reporter
+            .set_pending_update(Some(pending_update.clone()));
+        self.pending_update = Some(pending_update);
+    }
+
+    /// Delay until the changes held back may be applied, if any are held.
+    fn release_delay(&self, update_schedule: &UpdateSchedule) -> Option<Duration> {
+        self.pending_update.as_ref()?;
+
+        let now = Utc::now();
+        match update_schedule.freeze_end(now) {
+            Some(freeze_end) => Some((freeze_end - now).to_std().unwrap_or_default()),
+            None => Some(Duration::ZERO),
+        }
+    }
+
+    /// Drops the changes held back, once applied or gone.
+    fn discard_pending_update(&mut self) {
+        if self.pending_update.take().is_some() {
+            self.status_reporter.set_pending_update(None);
+        }
+    }
+
+    /// Interval until the next update while polling.
+    fn poll_interval(&self, config: &DefinitionDownloaderConfig) -> Duration {
+        let update_interval = Duration::from_secs(config.update_interval_seconds);
+
+        match (&config.adaptive_polling, self.adaptive_interval) {
+            (Some(_), Some(adaptive_interval)) => adaptive_interval.min(update_interval),
+            (Some(adaptive_polling), None) => {
+                Duration::from_secs(adaptive_polling.min_interval_seconds)
+            }
+            (None, _) => update_interval,
+        }
+    }
+
+    /// Polls at the shortest interval right after a change, growing the interval otherwise.
+    fn adapt_poll_interval(&mut self, changed: bool) {
+        let config = self.config();
+
+        let adaptive_polling = match &config.adaptive_polling {
+            Some(adaptive_polling) => adaptive_polling,
+            None => {
+                self.adaptive_interval = None;
+                return;
+            }
+        };
+
+        let min_interval = Duration::from_secs(adaptive_polling.min_interval_seconds);
+        let update_interval = Duration::from_secs(config.update_interval_seconds);
+
+        let interval = if changed {
+            min_interval
+        } else {
+            self.poll_interval(&config)
+                .mul_f64(adaptive_polling.multiplier)
+                .clamp(min_interval, update_interval.max(min_interval))
+        };
+
+        log::debug!("next update in {:?}", interval);
+        self.adaptive_interval = Some(interval);
+    }
+
+    /// Runs a source operation on the blocking pool, giving it up after `operation_timeout`.
+    ///
+    /// A blocking operation cannot be interrupted, so a timed out operation is cancelled and keeps
+    /// running in the background, with its result discarded, until it notices. The next operation
+    /// waits for it to stop for up to `CANCELLATION_GRACE_PERIOD`, and is given up if it does not,
+    /// so that two operations never touch the definitions at the same time.
+    async fn run_blocking<T, F>(
+        &mut self,
+        operation_name: &str,
+        operation_timeout: Duration,
+        operation: F,
+    ) -> Result<T, Error>
+    where
+        T: Send + 'static,
+        F: FnOnce(&dyn DefinitionSource) -> Result<T, Error> + Send + 'static,
+    {
+        if let Some(mut abandoned_operation) = self.abandoned_operation.take() {
+            log::warn!("waiting for the previously timed out source operation to stop");
+
+            if timeout(CANCELLATION_GRACE_PERIOD, &mut abandoned_operation)
+                .await
+                .is_err()
+            {
+                self.abandoned_operation = Some(abandoned_operation);
+
+                let message = format!(
+                    "a timed out source operation is still running {:?} after being cancelled, skipping source {}",
+                    CANCELLATION_GRACE_PERIOD, operation_name
                 );
+                self.status_reporter
+                    .set_degraded(Stage::Downloader, message.as_str());
 
-                self.try_update().await;
+                return Err(Error::new(ErrorKind::TaskFailure, message.as_str()));
             }
         }
+
+        let source = self.source.clone();
+        let mut handle = task::spawn_blocking(move || operation(source.as_ref()));
+
+        match timeout(operation_timeout, &mut handle).await {
+            Ok(Ok(result)) => result,
+            Ok(Err(error)) => Err(Error::new(
+                ErrorKind::TaskFailure,
+                format!("source {} failed to complete: {}", operation_name, error).as_str(),
+            )),
+            Err(_) => {
+                self.source.cancel();
+                self.abandoned_operation = Some(tokio::spawn(async move {
+                    let _ = handle.await;
+                }));
+
+                Err(Error::new(
+                    ErrorKind::FailedToUpdateDefinitions,
+                    format!(
+                        "source {} timed out after {:?}",
+                        operation_name, operation_timeout
+                    )
+                    .as_str(),
+                ))
+            }
+        }
+    }
+}
+
+async fn wait_for_change(
+    directory_watcher: &mut Option<DirectoryWatcher>,
+    debounce: Duration,
+) -> bool {
+    match directory_watcher {
+        Some(directory_watcher) => directory_watcher.changed(debounce).await,
+        None => false,
     }
 }
@@ -1,10 +1,29 @@
+use std::sync::Arc;
+
+use super::definition_snapshot::DefinitionSnapshot;
+
 #[derive(Debug)]
 pub struct DownloaderState {
     pub available: bool,
+    pub revision: Option<String>,
+    /// Definitions of `revision`, shared with the reader until a newer revision replaces them.
+    pub snapshot: Option<Arc<DefinitionSnapshot>>,
 }
 
 impl DownloaderState {
-    pub fn new(available: bool) -> DownloaderState {
-        DownloaderState { available }
+    pub fn new(available: bool, revision: String, snapshot: DefinitionSnapshot) -> DownloaderState {
+        DownloaderState {
+            available,
+            revision: Some(revision),
+            snapshot: Some(Arc::new(snapshot)),
+        }
+    }
+
+    pub fn new_not_available() -> DownloaderState {
+        DownloaderState {
+            available: false,
+            revision: None,
+            snapshot: None,
+        }
     }
 }
@@ -1,47 +1,108 @@
-use cooplan_definition_git_downloader::version_detector::VersionDetector;
+use std::{collections::HashMap, sync::Arc};
+
 use cooplan_definitions_io_lib::category_file_io::build_for_all_categories;
 use cooplan_definitions_lib::{
     definition::Definition, validated_source_category::ValidatedSourceCategory,
 };
 use tokio::sync::watch::{Receiver, Sender};
 
-use crate::{definition::downloader_state::DownloaderState, definition::reader_state::ReaderState};
+use crate::{
+    definition::definition_snapshot::DefinitionSnapshot,
+    definition::downloader_state::DownloaderState, definition::reader_state::ReaderState,
+};
+
+/// Directory the definitions are read from.
+pub const CATEGORIES_PATH: &str = "./categories/";
 
-/// Retrieves the definitions from a local directory, whenever the downloader downloads or updates that directory.
+/// Retrieves the definitions from the snapshot the downloader publishes whenever it downloads or
+/// updates them.
+///
+/// The version of the definitions is the revision of the snapshot.
 pub struct FileReader {
-    path: String,
     state_sender: Sender<ReaderState>,
     downloader_state_receiver: Receiver<DownloaderState>,
-    version_detector: VersionDetector,
 }
 
 impl FileReader {
     pub fn new(
-        path: String,
         state_sender: Sender<ReaderState>,
         downloader_state_receiver: Receiver<DownloaderState>,
-        version_detector: VersionDetector,
     ) -> FileReader {
         FileReader {
-            path,
             state_sender,
             downloader_state_receiver,
-            version_detector,
         }
     }
 
     pub async fn run(&mut self) {
         loop {
-            self.downloader_state_receiver.changed().await;
+            if self.downloader_state_receiver.changed().await.is_err() {
+                log::warn!("downloader state sender has been dropped");
+                return;
+            }
+
+            // The snapshot is kept alive while being read, even if a newer one replaces it.
+            let snapshot = match &*self.downloader_state_receiver.borrow() {
+                DownloaderState {
+                    available: true,
+                    revision: Some(revision),
+                    snapshot: Some(snapshot),
+                } => Some((revision.clone(), snapshot.clone())),
+                _ => None,
+            };
 
-            if self.downloader_state_receiver.borrow().available {
-                self.read();
+            if let Some((revision, snapshot)) = snapshot {
+                self.read(revision, snapshot);
             }
         }
     }
 
-    fn read(&self) {
-        match build_for_all_categories(self.path.clone()) {
+    /// Reads and merges the categories of every directory of the snapshot, refusing categories
+    /// whose id is defined by more than one directory.
+    fn read(&self, version: String, snapshot: Arc<DefinitionSnapshot>) {
+        let mut categories: Vec<ValidatedSourceCategory> = Vec::new();
+        let mut category_paths: HashMap<String, String> = HashMap::new();
+        let mut duplicates: Vec<String> = Vec::new();
+
+        for (path, snapshot_path) in snapshot.paths() {
+            let directory_categories = match self.read_directory(snapshot_path.as_str()) {
+                Some(directory_categories) => directory_categories,
+                None => {
+                    self.set_not_available();
+                    return;
+                }
+            };
+
+            for category in directory_categories {
+                match category_paths.get(&category.id) {
+                    Some(other_path) => duplicates.push(format!(
+                        "'{}' in '{}' and '{}'",
+                        category.id, other_path, path
+                    )),
+                    None => {
+                        category_paths.insert(category.id.clone(), path.clone());
+                    }
+                }
+
+                categories.push(category);
+            }
+        }
+
+        if !duplicates.is_empty() {
+            log::error!("duplicate category ids: {}", duplicates.join(", "));
+            self.set_not_available();
+            return;
+        }
+
+        log::info!("version detected: {}", version);
+        let definition = Definition::new(version, categories);
+
+        self.state_sender
+            .send_replace(ReaderState::new(true, definition));
+    }
+
+    fn read_directory(&self, path: &str) -> Option<Vec<ValidatedSourceCategory>> {
+        match build_for_all_categories(path.to_string()) {
             Ok(categories_io) => {
                 let mut categories: Vec<ValidatedSourceCategory> = Vec::new();
 
@@ -52,50 +113,30 @@ impl FileReader {
                                 Ok(category) => categories.push(category),
                                 Err(error) => {
                                     log::error!("failed to validate source category: {}", error);
-
-                                    if !self.state_sender.borrow().available {
-                                        self.state_sender
-                                            .send_replace(ReaderState::new_not_available());
-                                    }
-
-                                    return;
+                                    return None;
                                 }
                             }
                         }
                         Err(error) => {
                             log::error!("failed to read category: {}", error);
-
-                            if !self.state_sender.borrow().available {
-                                self.state_sender
-                                    .send_replace(ReaderState::new_not_available());
-                            }
-
-                            return;
+                            return None;
                         }
                     }
                 }
 
-                match self.version_detector.read_version() {
-                    Ok(version) => {
-                        log::info!("version detected: {}", version);
-                        let definition = Definition::new(version, categories);
-
-                        self.state_sender
-                            .send_replace(ReaderState::new(true, definition));
-                    }
-                    Err(error) => {
-                        log::error!("failed to read definition's version: {}", error);
-                    }
-                }
+                Some(categories)
             }
             Err(error) => {
                 log::error!("failed to read category: {}", error);
-
-                if !self.state_sender.borrow().available {
-                    self.state_sender
-                        .send_replace(ReaderState::new_not_available());
-                }
+                None
             }
         }
     }
+
+    fn set_not_available(&self) {
+        if !self.state_sender.borrow().available {
+            self.state_sender
+                .send_replace(ReaderState::new_not_available());
+        }
+    }
 }
@@ -0,0 +1,203 @@
+use std::{
+    io::Error,
+    path::PathBuf,
+    sync::{Mutex, OnceLock},
+};
+
+use git2::{cert::Cert, Cred, CredentialType, ErrorClass, ErrorCode, RemoteCallbacks};
+
+use crate::{
+    config::git_config::{is_ssh_url, GitConfig},
+    secret,
+};
+
+use super::known_hosts::{self, KnownHosts};
+
+const ENV_GIT_USERNAME: &str = "GIT_USERNAME";
+const ENV_GIT_PASSWORD: &str = "GIT_PASSWORD";
+
+/// Username used when neither the config nor the URL name one, as expected by most git hosts.
+const DEFAULT_USERNAME: &str = "git";
+
+/// Credentials found within the environment the process started with.
+static PROCESS_CREDENTIALS: OnceLock<(Option<String>, Option<String>)> = OnceLock::new();
+
+/// Credentials of a single repository, along with the host keys trusted for it.
+pub struct GitCredentials {
+    username: Option<String>,
+    password: Option<String>,
+    ssh_key: Option<PathBuf>,
+    ssh_passphrase: Option<String>,
+    known_hosts: Option<KnownHosts>,
+
+    /// Reason the host key was rejected during the last remote operation, since git only reports
+    /// that the check failed.
+    host_key_rejection: Mutex<Option<String>>,
+}
+
+impl GitCredentials {
+    /// Resolves the credentials of `git_config`.
+    ///
+    /// The username is taken from the config, then from `GIT_USERNAME`. The password or token is
+    /// taken from `password_file` if configured, then from the file referenced by
+    /// `GIT_PASSWORD_FILE`, and finally from `GIT_PASSWORD`.
+    pub fn resolve(git_config: &GitConfig) -> Result<GitCredentials, Error> {
+        let (process_username, process_password) = PROCESS_CREDENTIALS.get_or_init(|| {
+            (
+                std::env::var(ENV_GIT_USERNAME).ok(),
+                secret::read_env(ENV_GIT_PASSWORD).ok(),
+            )
+        });
+
+        let username = git_config.username.clone().or(process_username.clone());
+
+        let password = match git_config.password()? {
+            Some(password) => Some(password),
+            None => process_password.clone(),
+        };
+
+        if let Some(password) = &password {
+            secret::register(password.as_str());
+        }
+
+        let known_hosts = match &git_config.known_hosts_file {
+            Some(known_hosts_file) => Some(KnownHosts::read(known_hosts_file.as_str())?),
+            None => None,
+        };
+
+        Ok(GitCredentials {
+            username,
+            password,
+            ssh_key: git_config.ssh_key_file.as_ref().map(PathBuf::from),
+            ssh_passphrase: git_config.ssh_passphrase()?,
+            known_hosts,
+            host_key_rejection: Mutex::new(None),
+        })
+    }
+
+    /// Callbacks authenticating against the host of `repository_url`, and checking its host key if
+    /// it is reached through SSH and known hosts are configured. TLS certificates are left to
+    /// libgit2.
+    pub fn remote_callbacks(&self, repository_url: &str) -> RemoteCallbacks<'_> {
+        let mut callbacks = RemoteCallbacks::new();
+
+        // Git asks again whenever credentials are rejected, so each kind is only offered once.
+        let mut offered = CredentialType::empty();
+        callbacks.credentials(move |url, username_from_url, allowed_types| {
+            self.credentials(url, username_from_url, allowed_types, &mut offered)
+        });
+
+        let known_hosts = self
+            .known_hosts
+            .as_ref()
+            .filter(|_| is_ssh_url(repository_url));
+
+        if let Some(known_hosts) = known_hosts {
+            let ssh_port = known_hosts::ssh_port(repository_url);
+            callbacks.certificate_check(move |cert, host| {
+                self.check_host_key(known_hosts, cert, host, ssh_port)
+            });
+        }
+
+        callbacks
+    }
+
+    /// Reason the host key was rejected since the last call, if it was.
+    pub fn take_host_key_rejection(&self) -> Option<String> {
+        match self.host_key_rejection.lock() {
+            Ok(mut host_key_rejection) => host_key_rejection.take(),
+            Err(_) => None,
+        }
+    }
+
+    fn credentials(
+        &self,
+        url: &str,
+        username_from_url: Option<&str>,
+        allowed_types: CredentialType,
+        offered: &mut CredentialType,
+    ) -> Result<Cred, git2::Error> {
+        // The user of SSH URLs, such as `git@host:repository`, is part of the address itself.
+        let ssh_username = username_from_url
+            .or(self.username.as_deref())
+            .unwrap_or(DEFAULT_USERNAME);
+        let username = self
+            .username
+            .as_deref()
+            .or(username_from_url)
+            .unwrap_or(DEFAULT_USERNAME);
+
+        if allowed_types.contains(CredentialType::USERNAME)
+            && !offered.contains(CredentialType::USERNAME)
+        {
+            offered.insert(CredentialType::USERNAME);
+            return Cred::username(ssh_username);
+        }
+
+        if let Some(ssh_key) = &self.ssh_key {
+            if allowed_types.contains(CredentialType::SSH_KEY)
+                && !offered.contains(CredentialType::SSH_KEY)
+            {
+                offered.insert(CredentialType::SSH_KEY);
+                return Cred::ssh_key(
+                    ssh_username,
+                    None,
+                    ssh_key.as_path(),
+                    self.ssh_passphrase.as_deref(),
+                );
+            }
+        }
+
+        if let Some(password) = &self.password {
+            if allowed_types.contains(CredentialType::USER_PASS_PLAINTEXT)
+                && !offered.contains(CredentialType::USER_PASS_PLAINTEXT)
+            {
+                offered.insert(CredentialType::USER_PASS_PLAINTEXT);
+                return Cred::userpass_plaintext(username, password.as_str());
+            }
+        }
+
+        let message = if offered.is_empty() {
+            format!("no credentials configured are accepted by '{}'", url)
+        } else {
+            format!("credentials were rejected by '{}'", url)
+        };
+
+        Err(git2::Error::new(
+            ErrorCode::Auth,
+            ErrorClass::Callback,
+            message.as_str(),
+        ))
+    }
+
+    fn check_host_key(
+        &self,
+        known_hosts: &KnownHosts,
+        cert: &Cert<'_>,
+        host: &str,
+        ssh_port: Option<u16>,
+    ) -> bool {
+        // Only installed for SSH URLs, so a missing host key is never a TLS certificate.
+        let result = match cert
+            .as_hostkey()
+            .and_then(|host_key| host_key.hash_sha256())
+        {
+            Some(key_hash) => known_hosts.verify(host, ssh_port, key_hash),
+            None => Err(format!(
+                "'{}' did not present an SSH host key to check against the known hosts",
+                host
+            )),
+        };
+
+        match result {
+            Ok(_) => true,
+            Err(rejection) => {
+                if let Ok(mut host_key_rejection) = self.host_key_rejection.lock() {
+                    *host_key_rejection = Some(rejection);
+                }
+
+                false
+            }
+        }
+    }
+}
@@ -0,0 +1,696 @@
+use std::sync::{atomic::Ordering, Mutex};
+
+use cooplan_definition_git_downloader::version_detector::VersionDetector;
+
+use git2::{Oid, Repository};
+
+use crate::{
+    config::git_config::{GitConfig, GitPin, HistoryRewritePolicy},
+    error::{Error, ErrorKind},
+};
+
+use super::{
+    definition_source::DefinitionSource, git_credentials::GitCredentials, git_repository,
+    operation_guard::OperationGuard, signature_verifier::SignatureVerifier,
+};
+
+/// Clones a git repository and follows its branch, or the revision it is pinned to, identifying
+/// revisions by the commit of `HEAD`.
+///
+/// The repository is reached through the last mirror which could be reached, failing over to the
+/// next ones in order. A clone which turns out to be broken is cloned again, as is one whose
+/// branch was rewritten upstream if the config allows it.
+pub struct GitDefinitionSource {
+    git_config: GitConfig,
+    credentials: GitCredentials,
+    signature_verifier: Option<SignatureVerifier>,
+    version_detector: VersionDetector,
+    directory: String,
+    operation_guard: OperationGuard,
+
+    /// Index of the last mirror which could be reached among the repository URLs.
+    healthy_mirror: Mutex<usize>,
+    /// Commit the last check for changes found a refresh would move to.
+    available_commit: Mutex<Option<String>>,
+}
+
+impl GitDefinitionSource {
+    pub fn new(git_config: &GitConfig, directory: &str) -> Result<GitDefinitionSource, Error> {
+        let credentials = match GitCredentials::resolve(git_config) {
+            Ok(credentials) => credentials,
+            Err(error) => {
+                return Err(Error::new(
+                    ErrorKind::FailedToReadEnvironmentVariables,
+                    format!(
+                        "failed to read credentials of '{}': {}",
+                        git_config.repository_local_dir, error
+                    )
+                    .as_str(),
+                ))
+            }
+        };
+
+        Ok(GitDefinitionSource {
+            git_config: git_config.clone(),
+            credentials,
+            signature_verifier: git_config
+                .signature_verification
+                .as_ref()
+                .map(SignatureVerifier::new),
+            version_detector: VersionDetector::new(git_config.repository_local_dir.clone()),
+            directory: directory.to_string(),
+            operation_guard: OperationGuard::new(git_config.repository_local_dir.as_str()),
+            healthy_mirror: Mutex::new(0),
+            available_commit: Mutex::new(None),
+        })
+    }
+
+    fn set_available_commit(&self, commit: Option<String>) {
+        if let Ok(mut available_commit) = self.available_commit.lock() {
+            *available_commit = commit;
+        }
+    }
+
+    /// Opens the clone, reporting it as corrupted if it is broken, so that it gets repaired.
+    fn open_clone(&self) -> Result<Repository, Error> {
+        let repository_local_dir = self.git_config.repository_local_dir.as_str();
+        self.check_is_clone()?;
+
+        match git_repository::open_healthy(repository_local_dir) {
+            Ok(repository) => Ok(repository),
+            Err(error) if error.kind() == ErrorKind::RepositoryCorrupted => Err(Error::new(
+                ErrorKind::RepositoryCorrupted,
+                format!("clone '{}' is broken: {}", repository_local_dir, error).as_str(),
+            )),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Refuses to touch `repository_local_dir` unless it holds a clone, as it may hold anything
+    /// else if misconfigured.
+    fn check_is_clone(&self) -> Result<(), Error> {
+        let repository_local_dir = self.git_config.repository_local_dir.as_str();
+
+        if git_repository::is_clone(repository_local_dir) {
+            return Ok(());
+        }
+
+        Err(Error::new(
+            ErrorKind::FailedToOpenRepository,
+            format!(
+                "'{}' holds no clone, refusing to replace it; remove it or point \
+                 repository_local_dir elsewhere",
+                repository_local_dir
+            )
+            .as_str(),
+        ))
+    }
+
+    /// Clones the repository from the first mirror which can be reached and checks out the
+    /// revision the config asks for.
+    fn clone_repository(&self) -> Result<(), Error> {
+        let (_, mirror) = self.on_mirrors(|_, repository_url| {
+            git_repository::clone(
+                repository_url,
+                self.git_config.repository_local_dir.as_str(),
+                self.git_config.remote_name.as_str(),
+                self.git_config.remote_branch.as_str(),
+                &self.credentials,
+                self.operation_guard.cancelled(),
+            )
+        })?;
+
+        match &self.git_config.pin {
+            Some(pin) => self.check_out_pin(pin),
+            None => {
+                self.follow_branch()?;
+
+                let repository =
+                    git_repository::open(self.git_config.repository_local_dir.as_str())?;
+                let head = git_repository::head(&repository)?;
+
+                self.check_mirrors_agree(
+                    &repository,
+                    mirror,
+                    format!("refs/heads/{}", self.git_config.remote_branch).as_str(),
+                    head,
+                )?;
+                self.log_served(head, mirror);
+
+                Ok(())
+            }
+        }
+    }
+
+    /// Clones the repository from scratch in place of the clone, which is kept if cloning fails.
+    ///
+    /// The reader only reads snapshots, so the definitions it serves are not affected.
+    fn reclone(&self) -> Result<(), Error> {
+        self.check_is_clone()?;
+
+        self.clone_repository()
+    }
+
+    /// Pulls the followed branch, or moves to the revision the pin resolves to.
+    fn update(&self) -> Result<(), Error> {
+        match &self.git_config.pin {
+            Some(pin) => self.check_out_pin(pin),
+            None => self.pull_branch(),
+        }
+    }
+
+    /// Pulls the followed branch, or moves to the revision the pin resolves to, cloning the
+    /// repository again if the clone is broken or, if allowed, the branch was rewritten.
+    ///
+    /// The repository is cloned if `repository_local_dir` is empty or missing.
+    fn update_clone(&self) -> Result<(), Error> {
+        let is_empty = match std::fs::read_dir(self.git_config.repository_local_dir.as_str()) {
+            Ok(mut entries) => entries.next().is_none(),
+            Err(_) => true,
+        };
+
+        if is_empty {
+            return self.clone_repository();
+        }
+
+        let error = match self.update() {
+            Ok(_) => return Ok(()),
+            Err(error) => error,
+        };
+
+        match (error.kind(), self.git_config.history_rewrite) {
+            (ErrorKind::RepositoryCorrupted, _) => {
+                log::error!("{}, cloning the repository again", error);
+                self.reclone()
+            }
+            (ErrorKind::HistoryRewritten, HistoryRewritePolicy::Reclone) => {
+                log::warn!("{}, cloning the repository again", error);
+                self.reclone()
+            }
+            (ErrorKind::HistoryRewritten, HistoryRewritePolicy::Refuse) => Err(Error::new(
+                ErrorKind::HistoryRewritten,
+                format!("{}, refusing to follow it", error).as_str(),
+            )),
+            _ => Err(error),
+        }
+    }
+
+    /// Runs `operation` against the last mirror which could be reached, then against the other
+    /// ones in order until one succeeds, returning its result along with the index of the mirror
+    /// among the repository URLs.
+    fn on_mirrors<T, F>(&self, mut operation: F) -> Result<(T, usize), Error>
+    where
+        F: FnMut(usize, &str) -> Result<T, Error>,
+    {
+        let repository_urls = self.git_config.repository_urls();
+        let healthy_mirror = match self.healthy_mirror.lock() {
+            Ok(healthy_mirror) => *healthy_mirror,
+            Err(_) => 0,
+        };
+
+        let mut order: Vec<usize> = vec![healthy_mirror];
+        order.extend((0..repository_urls.len()).filter(|index| *index != healthy_mirror));
+
+        let mut failures: Vec<Error> = Vec::new();
+        for index in order {
+            let repository_url = repository_urls[index].as_str();
+
+            if self.operation_guard.cancelled().load(Ordering::SeqCst) {
+                return Err(Error::new(
+                    ErrorKind::OperationCancelled,
+                    format!("cancelled before reaching '{}'", repository_url).as_str(),
+                ));
+            }
+
+            match operation(index, repository_url) {
+                Ok(result) => {
+                    if index != healthy_mirror {
+                        log::warn!("failed over to mirror '{}'", repository_url);
+
+                        if let Ok(mut healthy_mirror) = self.healthy_mirror.lock() {
+                            *healthy_mirror = index;
+                        }
+                    }
+
+                    return Ok((result, index));
+                }
+                Err(error) => {
+                    if repository_urls.len() > 1 {
+                        log::warn!("mirror '{}' failed: {}", repository_url, error);
+                    }
+
+                    failures.push(error);
+                }
+            }
+        }
+
+        match failures.len() {
+            1 => Err(failures.remove(0)),
+            _ => Err(Error::new(
+                failures
+                    .last()
+                    .map_or(ErrorKind::ConnectionFailure, Error::kind),
+                format!(
+                    "every mirror failed: {}",
+                    failures
+                        .iter()
+                        .map(Error::to_string)
+                        .collect::<Vec<String>>()
+                        .join("; ")
+                )
+                .as_str(),
+            )),
+        }
+    }
+
+    /// Fetches the followed branch and the tags from the first mirror which can be reached,
+    /// returning the index of the mirror. Its tags are kept within its own namespace.
+    fn fetch_mirror(&self, repository: &Repository) -> Result<usize, Error> {
+        let (_, mirror) = self.on_mirrors(|mirror, repository_url| {
+            git_repository::fetch(
+                repository,
+                repository_url,
+                self.git_config.remote_name.as_str(),
+                self.git_config.remote_branch.as_str(),
+                git_repository::mirror_tags_namespace(mirror).as_str(),
+                &self.credentials,
+                self.operation_guard.cancelled(),
+            )
+        })?;
+
+        Ok(mirror)
+    }
+
+    fn mirror_url(&self, mirror: usize) -> String {
+        self.git_config
+            .repository_urls()
+            .get(mirror)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Logs the mirror `commit` was served by, if there are several.
+    fn log_served(&self, commit: Oid, mirror: usize) {
+        if !self.git_config.mirror_urls.is_empty() {
+            log::info!(
+                "revision {} served by mirror '{}'",
+                commit,
+                self.mirror_url(mirror)
+            );
+        }
+    }
+
+    /// Commit each mirror other than the one at `mirror` serves for `reference`, along with its
+    /// URL.
+    ///
+    /// Mirrors which cannot be reached, or do not have `reference` yet, are left out.
+    fn other_mirror_commits(
+        &self,
+        repository: &Repository,
+        mirror: usize,
+        reference: &str,
+    ) -> Result<Vec<(String, Oid)>, Error> {
+        let mut commits: Vec<(String, Oid)> = Vec::new();
+
+        for (index, repository_url) in self.git_config.repository_urls().iter().enumerate() {
+            if index == mirror {
+                continue;
+            }
+
+            let remote_heads = match git_repository::list_remote(
+                repository,
+                repository_url,
+                &self.credentials,
+                self.operation_guard.cancelled(),
+            ) {
+                Ok(remote_heads) => remote_heads,
+                Err(error) if error.kind() == ErrorKind::OperationCancelled => return Err(error),
+                Err(error) => {
+                    log::warn!(
+                        "cannot check what mirror '{}' serves for '{}': {}",
+                        repository_url,
+                        reference,
+                        error
+                    );
+                    continue;
+                }
+            };
+
+            if let Some(commit) = git_repository::remote_reference_commit(&remote_heads, reference)
+            {
+                commits.push((repository_url.clone(), commit));
+            }
+        }
+
+        Ok(commits)
+    }
+
+    /// Refuses `commit`, served for `reference` by the mirror at `mirror`, if another mirror serves
+    /// `reference` on another history: at any other commit for a tag, or for a branch, which a
+    /// mirror may lag behind on, at a known commit neither descending from `commit` nor being one
+    /// of its ancestors.
+    fn check_mirrors_agree(
+        &self,
+        repository: &Repository,
+        mirror: usize,
+        reference: &str,
+        commit: Oid,
+    ) -> Result<(), Error> {
+        let is_tag = reference.starts_with("refs/tags/");
+
+        for (repository_url, other_commit) in
+            self.other_mirror_commits(repository, mirror, reference)?
+        {
+            if other_commit == commit
+                || (!is_tag
+                    && git_repository::related(repository, commit, other_commit) != Some(false))
+            {
+                continue;
+            }
+
+            return Err(Error::new(
+                ErrorKind::VersionSetFailure,
+                format!(
+                    "mirrors disagree on '{}': '{}' serves {}, '{}' serves {}",
+                    reference,
+                    self.mirror_url(mirror),
+                    commit,
+                    repository_url,
+                    other_commit
+                )
+                .as_str(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Whether the mirror at `mirror`, serving an ancestor of the followed branch, lags behind,
+    /// as another mirror still serves the branch at `head` or a descendant of it. Otherwise the
+    /// branch was rewound upstream.
+    fn is_mirror_lagging(
+        &self,
+        repository: &Repository,
+        mirror: usize,
+        head: Oid,
+    ) -> Result<bool, Error> {
+        let reference = format!("refs/heads/{}", self.git_config.remote_branch);
+
+        Ok(self
+            .other_mirror_commits(repository, mirror, reference.as_str())?
+            .iter()
+            .any(|(_, other_commit)| {
+                git_repository::descends_from(repository, *other_commit, head)
+            }))
+    }
+
+    /// Fetches the remote and checks out the commit `pin` currently resolves to, once the other
+    /// mirrors agree on the tag it is resolved through.
+    fn check_out_pin(&self, pin: &GitPin) -> Result<(), Error> {
+        let repository = self.open_clone()?;
+        let mirror = self.fetch_mirror(&repository)?;
+        let tags_namespace = git_repository::mirror_tags_namespace(mirror);
+
+        let (commit, tag) = git_repository::resolve_pin(&repository, pin, tags_namespace.as_str())?;
+
+        if let Some(tag) = &tag {
+            self.check_mirrors_agree(
+                &repository,
+                mirror,
+                format!("refs/tags/{}", tag).as_str(),
+                commit,
+            )?;
+        }
+
+        self.check_out_resolved(&repository, commit, tag.as_deref(), tags_namespace.as_str())?;
+        self.log_served(commit, mirror);
+
+        Ok(())
+    }
+
+    /// Checks out `commit`, which `tag` of `tags_namespace` was resolved to if any, trusting
+    /// the tag from now on.
+    fn check_out_resolved(
+        &self,
+        repository: &Repository,
+        commit: Oid,
+        tag: Option<&str>,
+        tags_namespace: &str,
+    ) -> Result<(), Error> {
+        self.verify(repository, commit, tag, tags_namespace)?;
+
+        if let Some(tag) = tag {
+            git_repository::accept_tag(repository, tags_namespace, tag)?;
+        }
+
+        git_repository::checkout(repository, commit)
+    }
+
+    /// Fetches the remote and fast-forwards the followed branch.
+    ///
+    /// The downloader's own pull merges the first fetched reference, which may be a tag instead of
+    /// the branch, so it is not relied on.
+    fn pull_branch(&self) -> Result<(), Error> {
+        let repository = self.open_clone()?;
+        let mirror = self.fetch_mirror(&repository)?;
+
+        let commit = git_repository::remote_branch_commit(
+            &repository,
+            self.git_config.remote_name.as_str(),
+            self.git_config.remote_branch.as_str(),
+        )?;
+
+        let branch = self.git_config.remote_branch.as_str();
+        let head = git_repository::head(&repository)?;
+        // Fast-forwarding reports a rewound branch as rewritten.
+        if !self.git_config.mirror_urls.is_empty()
+            && commit != head
+            && git_repository::branch_contains(&repository, branch, commit)
+            && self.is_mirror_lagging(&repository, mirror, head)?
+        {
+            log::warn!(
+                "mirror '{}' is behind at {}, keeping branch '{}'",
+                self.mirror_url(mirror),
+                commit,
+                branch
+            );
+
+            return git_repository::attach(&repository, branch);
+        }
+
+        if commit != head {
+            self.check_mirrors_agree(
+                &repository,
+                mirror,
+                format!("refs/heads/{}", branch).as_str(),
+                commit,
+            )?;
+        }
+
+        self.verify(
+            &repository,
+            commit,
+            None,
+            git_repository::LOCAL_TAGS_NAMESPACE,
+        )?;
+        git_repository::fast_forward(&repository, branch, commit)?;
+        self.log_served(commit, mirror);
+
+        Ok(())
+    }
+
+    /// Moves `HEAD` back to the followed branch, in case a previous pin detached it.
+    fn follow_branch(&self) -> Result<(), Error> {
+        let repository = git_repository::open(self.git_config.repository_local_dir.as_str())?;
+
+        git_repository::attach(&repository, self.git_config.remote_branch.as_str())?;
+
+        let head = git_repository::head(&repository)?;
+        self.verify(
+            &repository,
+            head,
+            None,
+            git_repository::LOCAL_TAGS_NAMESPACE,
+        )
+    }
+
+    /// Requires `commit`, or the `tag` of `tags_namespace` it was resolved through, to be signed by
+    /// a trusted key, if signature verification is configured.
+    fn verify(
+        &self,
+        repository: &Repository,
+        commit: Oid,
+        tag: Option<&str>,
+        tags_namespace: &str,
+    ) -> Result<(), Error> {
+        let signature_verifier = match &self.signature_verifier {
+            Some(signature_verifier) => signature_verifier,
+            None => return Ok(()),
+        };
+
+        let mut signatures = vec![(
+            format!("commit {}", commit),
+            git_repository::commit_signature(repository, commit)?,
+        )];
+        if let Some(tag) = tag {
+            signatures.push((
+                format!("tag '{}'", tag),
+                git_repository::tag_signature(repository, tags_namespace, tag)?,
+            ));
+        }
+
+        let mut failures: Vec<String> = Vec::new();
+        for (signed, signature) in signatures {
+            match signature {
+                Some(signature) => {
+                    match signature_verifier.verify(
+                        signature.signature.as_slice(),
+                        signature.signed_data.as_slice(),
+                    ) {
+                        Ok(_) => {
+                            log::info!("{} is signed by a trusted key", signed);
+                            return Ok(());
+                        }
+                        Err(error) => failures.push(format!("{}: {}", signed, error)),
+                    }
+                }
+                None => failures.push(format!("{} is not signed", signed)),
+            }
+        }
+
+        Err(Error::new(
+            ErrorKind::SignatureVerificationFailure,
+            format!("refusing untrusted revision, {}", failures.join("; ")).as_str(),
+        ))
+    }
+}
+
+impl DefinitionSource for GitDefinitionSource {
+    /// Clones the repository, or brings the clone of a previous run up to date, so that the
+    /// remote is reached either way.
+    fn fetch(&self) -> Result<(), Error> {
+        let _operation = self.operation_guard.begin()?;
+
+        self.update_clone()
+    }
+
+    /// Checks out the revision the config asks for within the clone of a previous run, without
+    /// fetching. Only the tags checked out before are trusted, as the mirrors cannot be checked.
+    fn restore(&self) -> Result<(), Error> {
+        let _operation = self.operation_guard.begin()?;
+
+        match &self.git_config.pin {
+            Some(pin) => {
+                let repository =
+                    git_repository::open(self.git_config.repository_local_dir.as_str())?;
+                let tags_namespace = git_repository::LOCAL_TAGS_NAMESPACE;
+
+                let (commit, tag) = git_repository::resolve_pin(&repository, pin, tags_namespace)?;
+                self.check_out_resolved(&repository, commit, tag.as_deref(), tags_namespace)
+            }
+            None => self.follow_branch(),
+        }
+    }
+
+    /// Brings the clone up to date, repairing it if needed.
+    fn refresh(&self) -> Result<(), Error> {
+        let _operation = self.operation_guard.begin()?;
+
+        self.update_clone()
+    }
+
+    /// Compares the commit the remote would move `HEAD` to with the current one, without
+    /// downloading any object.
+    fn has_changes(&self) -> Result<bool, Error> {
+        let _operation = self.operation_guard.begin()?;
+        self.set_available_commit(None);
+
+        let repository = match self.open_clone() {
+            Ok(repository) => repository,
+            Err(error) => {
+                // The refresh repairs it.
+                log::debug!("{}", error);
+                return Ok(true);
+            }
+        };
+        let head = git_repository::head(&repository)?;
+        let (remote_heads, mirror) = self.on_mirrors(|_, repository_url| {
+            git_repository::list_remote(
+                &repository,
+                repository_url,
+                &self.credentials,
+                self.operation_guard.cancelled(),
+            )
+        })?;
+
+        let target = match &self.git_config.pin {
+            None => {
+                let branch_reference = format!("refs/heads/{}", self.git_config.remote_branch);
+                let target = remote_heads
+                    .iter()
+                    .find(|(reference, _)| *reference == branch_reference)
+                    .map(|(_, oid)| *oid);
+
+                // A mirror lagging behind has nothing new to offer, unlike a rewound branch,
+                // which the refresh reports.
+                if let Some(target) = target {
+                    if target != head
+                        && !self.git_config.mirror_urls.is_empty()
+                        && git_repository::branch_contains(
+                            &repository,
+                            self.git_config.remote_branch.as_str(),
+                            target,
+                        )
+                        && self.is_mirror_lagging(&repository, mirror, head)?
+                    {
+                        return Ok(false);
+                    }
+                }
+
+                target
+            }
+            Some(GitPin::Tag(tag)) => git_repository::remote_tag_commit(&remote_heads, tag),
+            Some(GitPin::Commit(commit)) => {
+                if head.to_string().starts_with(commit.to_lowercase().as_str()) {
+                    return Ok(false);
+                }
+
+                self.set_available_commit(Some(commit.clone()));
+                return Ok(true);
+            }
+            Some(GitPin::Version(requirement)) => {
+                let tag_names = git_repository::remote_tag_names(&remote_heads);
+                let tag = git_repository::latest_matching_tag(&tag_names, requirement)?;
+
+                git_repository::remote_tag_commit(&remote_heads, tag.as_str())
+            }
+        };
+
+        if target == Some(head) {
+            return Ok(false);
+        }
+
+        // The refresh reports why the target cannot be found.
+        self.set_available_commit(target.map(|target| target.to_string()));
+        Ok(true)
+    }
+
+    fn available_revision(&self) -> Option<String> {
+        match self.available_commit.lock() {
+            Ok(available_commit) => available_commit.clone(),
+            Err(_) => None,
+        }
+    }
+
+    fn cancel(&self) {
+        self.operation_guard.cancel();
+    }
+
+    fn revision(&self) -> Result<String, Error> {
+        Ok(self.version_detector.read_version()?)
+    }
+
+    fn directories(&self) -> Vec<String> {
+        vec![self.directory.clone()]
+    }
+}
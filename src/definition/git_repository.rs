@@ -0,0 +1,810 @@
+use std::{
+    path::Path,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use git2::{
+    build::{CheckoutBuilder, RepoBuilder},
+    AutotagOption, Direction, ErrorClass, ErrorCode, FetchOptions, ObjectType, Oid,
+    RemoteCallbacks, Repository, TreeWalkMode, TreeWalkResult,
+};
+use semver::{Version, VersionReq};
+
+use crate::{
+    config::git_config::GitPin,
+    error::{Error, ErrorKind},
+};
+
+use super::{
+    directory_replacement::{self, sibling},
+    git_credentials::GitCredentials,
+};
+
+/// Namespace of the tags which have been checked out before, and are trusted as such.
+pub const LOCAL_TAGS_NAMESPACE: &str = "refs/tags";
+
+/// Suffix of the sibling directory a repository is cloned into before replacing the clone.
+const CLONING_SUFFIX: &str = ".cloning";
+/// Suffix of the sibling directory the replaced clone is moved to until the new one is in place.
+const PREVIOUS_SUFFIX: &str = ".previous";
+
+/// Lines starting the signature appended to the message of a signed tag.
+const TAG_SIGNATURE_MARKERS: [&str; 2] = [
+    "-----BEGIN PGP SIGNATURE-----",
+    "-----BEGIN SSH SIGNATURE-----",
+];
+
+/// Signature of a commit or tag and the data it signs.
+pub struct Signature {
+    pub signature: Vec<u8>,
+    pub signed_data: Vec<u8>,
+}
+
+pub fn open(repository_local_dir: &str) -> Result<Repository, Error> {
+    match Repository::open(repository_local_dir) {
+        Ok(repository) => Ok(repository),
+        Err(error) => Err(Error::new(
+            ErrorKind::FailedToOpenRepository,
+            format!(
+                "failed to open repository '{}': {}",
+                repository_local_dir, error
+            )
+            .as_str(),
+        )),
+    }
+}
+
+/// Whether `repository_local_dir` holds a clone, broken or not.
+pub fn is_clone(repository_local_dir: &str) -> bool {
+    Path::new(repository_local_dir).join(".git").exists()
+}
+
+/// Opens the clone within `repository_local_dir` and checks it is healthy, see [`check_health`].
+///
+/// Fails with [`ErrorKind::RepositoryCorrupted`] only if the clone is broken, rather than, for
+/// instance, locked by another process.
+pub fn open_healthy(repository_local_dir: &str) -> Result<Repository, Error> {
+    let repository = match Repository::open(repository_local_dir) {
+        Ok(repository) => repository,
+        Err(error) => {
+            return Err(Error::new(
+                broken_repository_kind(&error),
+                format!(
+                    "failed to open repository '{}': {}",
+                    repository_local_dir, error
+                )
+                .as_str(),
+            ))
+        }
+    };
+
+    check_health(&repository)?;
+
+    Ok(repository)
+}
+
+/// Clones `remote_branch` of `repository_url` into `repository_local_dir`, naming the remote
+/// `remote_name`. Stops once `cancelled` is set.
+///
+/// The repository is cloned beside `repository_local_dir` first, which is only replaced once the
+/// clone succeeds.
+pub fn clone(
+    repository_url: &str,
+    repository_local_dir: &str,
+    remote_name: &str,
+    remote_branch: &str,
+    credentials: &GitCredentials,
+    cancelled: &AtomicBool,
+) -> Result<(), Error> {
+    log::info!(
+        "cloning branch '{}' of '{}' into '{}'",
+        remote_branch,
+        repository_url,
+        repository_local_dir
+    );
+
+    let mut fetch_options = FetchOptions::new();
+    fetch_options.remote_callbacks(remote_callbacks(repository_url, credentials, cancelled));
+
+    let repository_dir = Path::new(repository_local_dir);
+    let (cloning_dir, previous_dir) = match (
+        sibling(repository_dir, CLONING_SUFFIX),
+        sibling(repository_dir, PREVIOUS_SUFFIX),
+    ) {
+        (Some(cloning_dir), Some(previous_dir)) => (cloning_dir, previous_dir),
+        _ => {
+            return Err(Error::new(
+                ErrorKind::FailedToCloneRepository,
+                format!("invalid repository directory '{}'", repository_local_dir).as_str(),
+            ))
+        }
+    };
+
+    // Left behind by a clone which was interrupted.
+    remove_dir(&cloning_dir)?;
+
+    let mut builder = RepoBuilder::new();
+    builder
+        .fetch_options(fetch_options)
+        .branch(remote_branch)
+        .remote_create(|repository, _, url| repository.remote(remote_name, url));
+
+    match builder.clone(repository_url, &cloning_dir) {
+        Ok(repository) => {
+            // Cloning always fetches every tag among the local ones, which are only to hold the
+            // tags checked against the other mirrors, see [`accept_tag`].
+            let result = remove_tags(&repository, LOCAL_TAGS_NAMESPACE)
+                .and_then(|_| replace_clone(repository_dir, &cloning_dir, &previous_dir));
+
+            if result.is_err() {
+                let _ = remove_dir(&cloning_dir);
+            }
+
+            result
+        }
+        Err(error) => {
+            let _ = remove_dir(&cloning_dir);
+
+            Err(remote_error(
+                ErrorKind::FailedToCloneRepository,
+                format!("failed to clone repository '{}'", repository_url).as_str(),
+                error,
+                credentials,
+                cancelled,
+            ))
+        }
+    }
+}
+
+/// Fetches `remote_branch` of `repository_url` into the remote-tracking branch of `remote_name`
+/// and every tag into `tags_namespace`, without touching the working tree.
+///
+/// The local tags are left untouched, so that the fetched ones can be checked before being
+/// trusted, see [`accept_tag`]. Stops once `cancelled` is set.
+pub fn fetch(
+    repository: &Repository,
+    repository_url: &str,
+    remote_name: &str,
+    remote_branch: &str,
+    tags_namespace: &str,
+    credentials: &GitCredentials,
+    cancelled: &AtomicBool,
+) -> Result<(), Error> {
+    let mut remote = match repository.remote_anonymous(repository_url) {
+        Ok(remote) => remote,
+        Err(error) => {
+            return Err(Error::new(
+                ErrorKind::FailedToUpdateDefinitions,
+                format!("invalid repository URL '{}': {}", repository_url, error).as_str(),
+            ))
+        }
+    };
+
+    let mut fetch_options = FetchOptions::new();
+    fetch_options.remote_callbacks(remote_callbacks(repository_url, credentials, cancelled));
+    fetch_options.download_tags(AutotagOption::None);
+
+    let branch_refspec = format!(
+        "+refs/heads/{}:{}",
+        remote_branch,
+        remote_tracking_reference(remote_name, remote_branch)
+    );
+
+    let tags_refspec = format!("+refs/tags/*:{}/*", tags_namespace);
+
+    match remote.fetch(
+        &[branch_refspec.as_str(), tags_refspec.as_str()],
+        Some(&mut fetch_options),
+        None,
+    ) {
+        Ok(_) => Ok(()),
+        Err(error) => Err(remote_error(
+            ErrorKind::FailedToUpdateDefinitions,
+            format!("failed to fetch from '{}'", repository_url).as_str(),
+            error,
+            credentials,
+            cancelled,
+        )),
+    }
+}
+
+/// Lists the references of `repository_url` and the commits they point to, like
+/// `git ls-remote`, without downloading any object.
+pub fn list_remote(
+    repository: &Repository,
+    repository_url: &str,
+    credentials: &GitCredentials,
+    cancelled: &AtomicBool,
+) -> Result<Vec<(String, Oid)>, Error> {
+    let mut remote = match repository.remote_anonymous(repository_url) {
+        Ok(remote) => remote,
+        Err(error) => {
+            return Err(Error::new(
+                ErrorKind::ConnectionFailure,
+                format!("invalid repository URL '{}': {}", repository_url, error).as_str(),
+            ))
+        }
+    };
+
+    let connection = match remote.connect_auth(
+        Direction::Fetch,
+        Some(remote_callbacks(repository_url, credentials, cancelled)),
+        None,
+    ) {
+        Ok(connection) => connection,
+        Err(error) => {
+            return Err(remote_error(
+                ErrorKind::ConnectionFailure,
+                format!("failed to connect to '{}'", repository_url).as_str(),
+                error,
+                credentials,
+                cancelled,
+            ))
+        }
+    };
+
+    match connection.list() {
+        Ok(remote_heads) => Ok(remote_heads
+            .iter()
+            .map(|remote_head| (remote_head.name().to_string(), remote_head.oid()))
+            .collect()),
+        Err(error) => Err(Error::new(
+            ErrorKind::ConnectionFailure,
+            format!(
+                "failed to list references of '{}': {}",
+                repository_url, error
+            )
+            .as_str(),
+        )),
+    }
+}
+
+/// Namespace the tags fetched from the mirror at `mirror` among the repository URLs are kept in.
+pub fn mirror_tags_namespace(mirror: usize) -> String {
+    format!("refs/mirrors/{}/tags", mirror)
+}
+
+/// Commit a tag listed by [`list_remote`] points to, peeling annotated tags.
+pub fn remote_tag_commit(remote_heads: &[(String, Oid)], tag: &str) -> Option<Oid> {
+    remote_reference_commit(remote_heads, format!("refs/tags/{}", tag).as_str())
+}
+
+/// Commit a reference listed by [`list_remote`] points to, peeling annotated tags.
+pub fn remote_reference_commit(remote_heads: &[(String, Oid)], reference: &str) -> Option<Oid> {
+    let peeled_reference = format!("{}^{{}}", reference);
+
+    let find = |name: &str| {
+        remote_heads
+            .iter()
+            .find(|(remote_reference, _)| remote_reference == name)
+            .map(|(_, oid)| *oid)
+    };
+
+    find(peeled_reference.as_str()).or_else(|| find(reference))
+}
+
+/// Tags listed by [`list_remote`].
+pub fn remote_tag_names(remote_heads: &[(String, Oid)]) -> Vec<String> {
+    remote_heads
+        .iter()
+        .filter_map(|(reference, _)| reference.strip_prefix("refs/tags/"))
+        .filter(|tag| !tag.ends_with("^{}"))
+        .map(String::from)
+        .collect()
+}
+
+/// Commit currently checked out.
+pub fn head(repository: &Repository) -> Result<Oid, Error> {
+    match repository.head().map(|head| head.target()) {
+        Ok(Some(oid)) => Ok(oid),
+        Ok(None) => Err(Error::new(
+            ErrorKind::VersionReadFailure,
+            "head does not reference a commit",
+        )),
+        Err(error) => Err(Error::new(
+            ErrorKind::VersionReadFailure,
+            format!("failed to read head: {}", error).as_str(),
+        )),
+    }
+}
+
+/// Commit of the remote-tracking branch of `remote_branch`, as of the last fetch.
+pub fn remote_branch_commit(
+    repository: &Repository,
+    remote_name: &str,
+    remote_branch: &str,
+) -> Result<Oid, Error> {
+    resolve_commit(
+        repository,
+        remote_tracking_reference(remote_name, remote_branch).as_str(),
+    )
+}
+
+/// Whether `commit` is `ancestor` or one of its known descendants.
+pub fn descends_from(repository: &Repository, commit: Oid, ancestor: Oid) -> bool {
+    commit == ancestor
+        || repository
+            .graph_descendant_of(commit, ancestor)
+            .unwrap_or(false)
+}
+
+/// Whether one of `commit` and `other_commit` descends from the other, or `None` if either of them
+/// is not known locally.
+pub fn related(repository: &Repository, commit: Oid, other_commit: Oid) -> Option<bool> {
+    if repository.find_commit(commit).is_err() || repository.find_commit(other_commit).is_err() {
+        return None;
+    }
+
+    Some(
+        commit == other_commit
+            || repository
+                .graph_descendant_of(commit, other_commit)
+                .unwrap_or(false)
+            || repository
+                .graph_descendant_of(other_commit, commit)
+                .unwrap_or(false),
+    )
+}
+
+/// Whether the local `branch` already contains `commit`, being at or ahead of it.
+pub fn branch_contains(repository: &Repository, branch: &str, commit: Oid) -> bool {
+    match repository.refname_to_id(format!("refs/heads/{}", branch).as_str()) {
+        Ok(current) => {
+            current == commit
+                || repository
+                    .graph_descendant_of(current, commit)
+                    .unwrap_or(false)
+        }
+        Err(_) => false,
+    }
+}
+
+/// Moves the local `branch` to `commit` and checks it out, refusing to do so unless `commit`
+/// descends from the commit the branch currently points to, as it would not if the branch was
+/// rewritten.
+pub fn fast_forward(repository: &Repository, branch: &str, commit: Oid) -> Result<(), Error> {
+    let branch_reference = format!("refs/heads/{}", branch);
+
+    if let Ok(current) = repository.refname_to_id(branch_reference.as_str()) {
+        if current == commit {
+            return attach(repository, branch);
+        }
+
+        if !repository
+            .graph_descendant_of(commit, current)
+            .unwrap_or(false)
+        {
+            return Err(Error::new(
+                ErrorKind::HistoryRewritten,
+                format!(
+                    "branch '{}' was rewritten upstream, {} does not descend from {}",
+                    branch, commit, current
+                )
+                .as_str(),
+            ));
+        }
+    }
+
+    log::info!("fast-forwarding branch '{}' to {}", branch, commit);
+
+    let result = repository
+        .reference(
+            branch_reference.as_str(),
+            commit,
+            true,
+            format!("fast-forward to {}", commit).as_str(),
+        )
+        .and_then(|_| repository.set_head(branch_reference.as_str()))
+        .and_then(|_| repository.checkout_head(Some(CheckoutBuilder::default().force())));
+
+    match result {
+        Ok(_) => Ok(()),
+        Err(error) => Err(Error::new(
+            ErrorKind::FailedToUpdateDefinitions,
+            format!("failed to fast-forward branch '{}': {}", branch, error).as_str(),
+        )),
+    }
+}
+
+/// Commit `pin` refers to within the already fetched repository, along with the tag it was
+/// resolved through, if any, looking tags up within `tags_namespace`.
+pub fn resolve_pin(
+    repository: &Repository,
+    pin: &GitPin,
+    tags_namespace: &str,
+) -> Result<(Oid, Option<String>), Error> {
+    match pin {
+        GitPin::Tag(tag) => Ok((
+            resolve_commit(repository, format!("{}/{}", tags_namespace, tag).as_str())?,
+            Some(tag.clone()),
+        )),
+        GitPin::Commit(commit) => Ok((resolve_commit(repository, commit.as_str())?, None)),
+        GitPin::Version(requirement) => {
+            let tag = latest_matching_tag(
+                tag_names(repository, tags_namespace)?.as_slice(),
+                requirement.as_str(),
+            )?;
+            log::info!("tag '{}' is the latest matching '{}'", tag, requirement);
+
+            Ok((
+                resolve_commit(repository, format!("{}/{}", tags_namespace, tag).as_str())?,
+                Some(tag),
+            ))
+        }
+    }
+}
+
+/// Trusts `tag` as found within `tags_namespace` from now on, copying it among the local tags.
+pub fn accept_tag(repository: &Repository, tags_namespace: &str, tag: &str) -> Result<(), Error> {
+    if tags_namespace == LOCAL_TAGS_NAMESPACE {
+        return Ok(());
+    }
+
+    let result = repository
+        .refname_to_id(format!("{}/{}", tags_namespace, tag).as_str())
+        .and_then(|oid| {
+            repository.reference(
+                format!("{}/{}", LOCAL_TAGS_NAMESPACE, tag).as_str(),
+                oid,
+                true,
+                format!("accept tag '{}'", tag).as_str(),
+            )
+        });
+
+    match result {
+        Ok(_) => Ok(()),
+        Err(error) => Err(Error::new(
+            ErrorKind::VersionSetFailure,
+            format!("failed to accept tag '{}': {}", tag, error).as_str(),
+        )),
+    }
+}
+
+/// Signature of `commit` and the data it signs, if the commit is signed.
+pub fn commit_signature(repository: &Repository, commit: Oid) -> Result<Option<Signature>, Error> {
+    match repository.extract_signature(&commit, None) {
+        Ok((signature, signed_data)) => Ok(Some(Signature {
+            signature: signature.to_vec(),
+            signed_data: signed_data.to_vec(),
+        })),
+        Err(error) if error.code() == ErrorCode::NotFound => Ok(None),
+        Err(error) => Err(Error::new(
+            ErrorKind::SignatureVerificationFailure,
+            format!("failed to read signature of commit {}: {}", commit, error).as_str(),
+        )),
+    }
+}
+
+/// Signature of the annotated `tag` found within `tags_namespace` and the data it signs, if the
+/// tag is signed.
+pub fn tag_signature(
+    repository: &Repository,
+    tags_namespace: &str,
+    tag: &str,
+) -> Result<Option<Signature>, Error> {
+    let tag_object = repository
+        .refname_to_id(format!("{}/{}", tags_namespace, tag).as_str())
+        .and_then(|oid| {
+            let odb = repository.odb()?;
+            let tag_object = odb.read(oid)?;
+            Ok((tag_object.kind(), tag_object.data().to_vec()))
+        });
+
+    let (kind, content) = match tag_object {
+        Ok(tag_object) => tag_object,
+        Err(error) => {
+            return Err(Error::new(
+                ErrorKind::SignatureVerificationFailure,
+                format!("failed to read tag '{}': {}", tag, error).as_str(),
+            ))
+        }
+    };
+
+    // Lightweight tags point straight to the commit and cannot be signed.
+    if kind != ObjectType::Tag {
+        return Ok(None);
+    }
+
+    // The signature is appended to the message of the tag.
+    let signature_start = TAG_SIGNATURE_MARKERS
+        .iter()
+        .filter_map(|marker| find_last(content.as_slice(), marker.as_bytes()))
+        .max();
+
+    match signature_start {
+        Some(start) => Ok(Some(Signature {
+            signature: content[start..].to_vec(),
+            signed_data: content[..start].to_vec(),
+        })),
+        None => Ok(None),
+    }
+}
+
+fn find_last(content: &[u8], pattern: &[u8]) -> Option<usize> {
+    content
+        .windows(pattern.len())
+        .rposition(|window| window == pattern)
+}
+
+/// Checks out `commit` with a detached `HEAD`, discarding local changes, unless already checked out.
+pub fn checkout(repository: &Repository, commit: Oid) -> Result<(), Error> {
+    if let Ok(head) = repository.head() {
+        if head.target() == Some(commit) && repository.head_detached().unwrap_or(false) {
+            return Ok(());
+        }
+    }
+
+    log::info!("checking out commit {}", commit);
+
+    let result = repository.set_head_detached(commit).and_then(|_| {
+        repository.checkout_head(Some(
+            // There are not supposed to be local changes within the definitions.
+            CheckoutBuilder::default().force(),
+        ))
+    });
+
+    match result {
+        Ok(_) => Ok(()),
+        Err(error) => Err(Error::new(
+            ErrorKind::VersionSetFailure,
+            format!("failed to check out commit {}: {}", commit, error).as_str(),
+        )),
+    }
+}
+
+/// Checks out the local `branch` if `HEAD` is detached.
+pub fn attach(repository: &Repository, branch: &str) -> Result<(), Error> {
+    if !repository.head_detached().unwrap_or(false) {
+        return Ok(());
+    }
+
+    log::info!("checking out branch '{}'", branch);
+
+    let result = repository
+        .set_head(format!("refs/heads/{}", branch).as_str())
+        .and_then(|_| repository.checkout_head(Some(CheckoutBuilder::default().force())));
+
+    match result {
+        Ok(_) => Ok(()),
+        Err(error) => Err(Error::new(
+            ErrorKind::VersionSetFailure,
+            format!("failed to check out branch '{}': {}", branch, error).as_str(),
+        )),
+    }
+}
+
+/// Checks that the commit checked out and every object of its tree are there, so that a broken
+/// clone is told apart from a remote which cannot be reached.
+fn check_health(repository: &Repository) -> Result<(), Error> {
+    let tree = match repository.head().and_then(|head| head.peel_to_tree()) {
+        Ok(tree) => tree,
+        Err(error) => {
+            return Err(Error::new(
+                broken_repository_kind(&error),
+                format!("failed to read the tree checked out: {}", error).as_str(),
+            ))
+        }
+    };
+
+    let odb = match repository.odb() {
+        Ok(odb) => odb,
+        Err(error) => {
+            return Err(Error::new(
+                broken_repository_kind(&error),
+                format!("failed to open the object database: {}", error).as_str(),
+            ))
+        }
+    };
+
+    let mut missing: Option<String> = None;
+    let _ = tree.walk(TreeWalkMode::PreOrder, |directory, entry| {
+        // Submodules point to commits of other repositories.
+        if entry.kind() == Some(ObjectType::Commit) || odb.exists(entry.id()) {
+            return TreeWalkResult::Ok;
+        }
+
+        missing = Some(format!("{}{}", directory, entry.name().unwrap_or_default()));
+        TreeWalkResult::Abort
+    });
+
+    match missing {
+        Some(path) => Err(Error::new(
+            ErrorKind::RepositoryCorrupted,
+            format!("object of '{}' is missing", path).as_str(),
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Kind of an error met while reading a clone: [`ErrorKind::RepositoryCorrupted`], unless the
+/// error may go away by itself, such as a lock held by another process or a failing system call.
+fn broken_repository_kind(error: &git2::Error) -> ErrorKind {
+    if error.code() == ErrorCode::Locked || error.class() == ErrorClass::Os {
+        return ErrorKind::FailedToOpenRepository;
+    }
+
+    ErrorKind::RepositoryCorrupted
+}
+
+/// Moves the clone within `cloning_dir` to `repository_dir`, in place of the clone within it, if
+/// any, which is put back if the move fails.
+fn replace_clone(
+    repository_dir: &Path,
+    cloning_dir: &Path,
+    previous_dir: &Path,
+) -> Result<(), Error> {
+    match directory_replacement::replace_dir(repository_dir, cloning_dir, previous_dir) {
+        Ok(_) => Ok(()),
+        Err(error) => Err(Error::new(
+            ErrorKind::DataWritingFailure,
+            format!(
+                "failed to replace repository '{}': {}",
+                repository_dir.display(),
+                error
+            )
+            .as_str(),
+        )),
+    }
+}
+
+/// Removes `dir` along with everything within it, if it exists.
+fn remove_dir(dir: &Path) -> Result<(), Error> {
+    match directory_replacement::remove_dir(dir) {
+        Ok(_) => Ok(()),
+        Err(error) => Err(Error::new(
+            ErrorKind::DataWritingFailure,
+            format!("failed to remove '{}': {}", dir.display(), error).as_str(),
+        )),
+    }
+}
+
+/// Callbacks of an operation reaching `repository_url`, which abort the transfer once
+/// `cancelled` is set.
+fn remote_callbacks<'a>(
+    repository_url: &str,
+    credentials: &'a GitCredentials,
+    cancelled: &'a AtomicBool,
+) -> RemoteCallbacks<'a> {
+    let mut callbacks = credentials.remote_callbacks(repository_url);
+    callbacks.transfer_progress(|_| !cancelled.load(Ordering::SeqCst));
+    callbacks.sideband_progress(|_| !cancelled.load(Ordering::SeqCst));
+
+    callbacks
+}
+
+/// Error of an operation reaching the remote, telling cancellations, rejected credentials and
+/// host keys apart from other failures.
+fn remote_error(
+    kind: ErrorKind,
+    message: &str,
+    error: git2::Error,
+    credentials: &GitCredentials,
+    cancelled: &AtomicBool,
+) -> Error {
+    if cancelled.load(Ordering::SeqCst) {
+        return Error::new(
+            ErrorKind::OperationCancelled,
+            format!("{}: cancelled", message).as_str(),
+        );
+    }
+
+    if let Some(host_key_rejection) = credentials.take_host_key_rejection() {
+        return Error::new(
+            ErrorKind::AuthenticationFailure,
+            format!("{}: host key rejected: {}", message, host_key_rejection).as_str(),
+        );
+    }
+
+    match error.code() {
+        ErrorCode::Auth | ErrorCode::Certificate => Error::new(
+            ErrorKind::AuthenticationFailure,
+            format!("{}: authentication failed: {}", message, error).as_str(),
+        ),
+        _ => Error::new(kind, format!("{}: {}", message, error).as_str()),
+    }
+}
+
+fn remote_tracking_reference(remote_name: &str, remote_branch: &str) -> String {
+    format!("refs/remotes/{}/{}", remote_name, remote_branch)
+}
+
+fn resolve_commit(repository: &Repository, revision: &str) -> Result<Oid, Error> {
+    match repository
+        .revparse_single(revision)
+        .and_then(|object| object.peel_to_commit())
+    {
+        Ok(commit) => Ok(commit.id()),
+        Err(error) => Err(Error::new(
+            ErrorKind::VersionSetFailure,
+            format!("failed to resolve revision '{}': {}", revision, error).as_str(),
+        )),
+    }
+}
+
+/// Tag of `tag_names` with the highest version matching the semver `requirement`.
+pub fn latest_matching_tag(tag_names: &[String], requirement: &str) -> Result<String, Error> {
+    let requirement = match VersionReq::parse(requirement) {
+        Ok(requirement) => requirement,
+        Err(error) => {
+            return Err(Error::new(
+                ErrorKind::VersionSetFailure,
+                format!("invalid version requirement '{}': {}", requirement, error).as_str(),
+            ))
+        }
+    };
+
+    let mut latest: Option<(Version, &String)> = None;
+    for tag in tag_names {
+        let version = match Version::parse(tag.strip_prefix('v').unwrap_or(tag)) {
+            Ok(version) => version,
+            Err(_) => continue,
+        };
+
+        if !requirement.matches(&version) {
+            continue;
+        }
+
+        let newer = match &latest {
+            Some((latest_version, _)) => version > *latest_version,
+            None => true,
+        };
+
+        if newer {
+            latest = Some((version, tag));
+        }
+    }
+
+    match latest {
+        Some((_, tag)) => Ok(tag.clone()),
+        None => Err(Error::new(
+            ErrorKind::VersionSetFailure,
+            format!("no tag matches version requirement '{}'", requirement).as_str(),
+        )),
+    }
+}
+
+/// Names of the tags found within `tags_namespace`.
+fn tag_names(repository: &Repository, tags_namespace: &str) -> Result<Vec<String>, Error> {
+    let prefix = format!("{}/", tags_namespace);
+
+    let references = match repository.references_glob(format!("{}*", prefix).as_str()) {
+        Ok(references) => references,
+        Err(error) => {
+            return Err(Error::new(
+                ErrorKind::VersionSetFailure,
+                format!("failed to list tags: {}", error).as_str(),
+            ))
+        }
+    };
+
+    Ok(references
+        .flatten()
+        .filter_map(|reference| {
+            reference
+                .name()
+                .and_then(|name| name.strip_prefix(prefix.as_str()))
+                .map(String::from)
+        })
+        .collect())
+}
+
+/// Removes the tags found within `tags_namespace`.
+fn remove_tags(repository: &Repository, tags_namespace: &str) -> Result<(), Error> {
+    let result = repository
+        .references_glob(format!("{}/*", tags_namespace).as_str())
+        .and_then(|references| {
+            for mut reference in references.flatten() {
+                reference.delete()?;
+            }
+
+            Ok(())
+        });
+
+    match result {
+        Ok(_) => Ok(()),
+        Err(error) => Err(Error::new(
+            ErrorKind::FailedToCloneRepository,
+            format!("failed to remove tags: {}", error).as_str(),
+        )),
+    }
+}
@@ -0,0 +1,390 @@
+use std::io::{Error, ErrorKind};
+
+use data_encoding::BASE64;
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+/// Port SSH hosts are listed without within `known_hosts`.
+const DEFAULT_SSH_PORT: u16 = 22;
+
+const HASHED_HOST_PREFIX: &str = "|1|";
+const REVOKED_MARKER: &str = "@revoked";
+const CERT_AUTHORITY_MARKER: &str = "@cert-authority";
+
+/// Host keys trusted for SSH remotes, read from a file in the OpenSSH `known_hosts` format.
+///
+/// Hashed host names are supported, certificate authorities are not.
+pub struct KnownHosts {
+    path: String,
+    entries: Vec<KnownHost>,
+}
+
+struct KnownHost {
+    hosts: Hosts,
+    /// SHA-256 of the host key, which is what git reports of the key presented by the host.
+    key_hash: Vec<u8>,
+    revoked: bool,
+}
+
+enum Hosts {
+    Hashed { salt: Vec<u8>, hash: Vec<u8> },
+    Patterns(Vec<String>),
+}
+
+impl KnownHosts {
+    pub fn read(path: &str) -> Result<KnownHosts, Error> {
+        match std::fs::read_to_string(path) {
+            Ok(content) => KnownHosts::parse(path, content.as_str()),
+            Err(error) => Err(Error::new(
+                error.kind(),
+                format!("failed to read known hosts file '{}': {}", path, error),
+            )),
+        }
+    }
+
+    /// Parses the `content` of the known hosts file found at `path`.
+    fn parse(path: &str, content: &str) -> Result<KnownHosts, Error> {
+        let mut entries: Vec<KnownHost> = Vec::new();
+
+        for (index, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            match parse_line(line) {
+                Ok(Some(entry)) => entries.push(entry),
+                Ok(None) => (),
+                Err(error) => {
+                    return Err(Error::new(
+                        ErrorKind::InvalidData,
+                        format!(
+                            "invalid line {} of known hosts file '{}': {}",
+                            index + 1,
+                            path,
+                            error
+                        ),
+                    ))
+                }
+            }
+        }
+
+        Ok(KnownHosts {
+            path: path.to_string(),
+            entries,
+        })
+    }
+
+    /// Checks that the key whose SHA-256 is `key_hash` is trusted for `host` listening on `port`.
+    pub fn verify(&self, host: &str, port: Option<u16>, key_hash: &[u8]) -> Result<(), String> {
+        // Like OpenSSH, which lowercases host names before hashing them.
+        let host = host.to_lowercase();
+        let host_name = match port {
+            Some(port) if port != DEFAULT_SSH_PORT => format!("[{}]:{}", host, port),
+            _ => host,
+        };
+
+        let entries: Vec<&KnownHost> = self
+            .entries
+            .iter()
+            .filter(|entry| entry.hosts.matches(host_name.as_str()))
+            .collect();
+
+        if entries
+            .iter()
+            .any(|entry| entry.revoked && entry.key_hash == key_hash)
+        {
+            return Err(format!("host key of '{}' is revoked", host_name));
+        }
+
+        if entries
+            .iter()
+            .any(|entry| !entry.revoked && entry.key_hash == key_hash)
+        {
+            return Ok(());
+        }
+
+        if entries.is_empty() {
+            return Err(format!(
+                "host '{}' is not listed in known hosts file '{}'",
+                host_name, self.path
+            ));
+        }
+
+        Err(format!(
+            "host key of '{}' does not match known hosts file '{}'",
+            host_name, self.path
+        ))
+    }
+}
+
+impl Hosts {
+    fn matches(&self, host_name: &str) -> bool {
+        match self {
+            Hosts::Hashed { salt, hash } => {
+                let mut mac = match Hmac::<Sha1>::new_from_slice(salt.as_slice()) {
+                    Ok(mac) => mac,
+                    Err(_) => return false,
+                };
+                mac.update(host_name.as_bytes());
+
+                mac.verify_slice(hash.as_slice()).is_ok()
+            }
+            Hosts::Patterns(patterns) => {
+                let mut matched = false;
+
+                for pattern in patterns {
+                    match pattern.strip_prefix('!') {
+                        Some(negated) if wildcard_match(negated, host_name) => return false,
+                        Some(_) => (),
+                        None => matched = matched || wildcard_match(pattern, host_name),
+                    }
+                }
+
+                matched
+            }
+        }
+    }
+}
+
+fn parse_line(line: &str) -> Result<Option<KnownHost>, String> {
+    let mut fields = line.split_whitespace();
+
+    let mut hosts = fields.next().unwrap_or_default();
+    let mut revoked = false;
+
+    if hosts.starts_with('@') {
+        match hosts {
+            REVOKED_MARKER => revoked = true,
+            CERT_AUTHORITY_MARKER => return Ok(None),
+            marker => return Err(format!("unknown marker '{}'", marker)),
+        }
+
+        hosts = fields.next().unwrap_or_default();
+    }
+
+    // The key type is part of the encoded key as well.
+    let key = match (fields.next(), fields.next()) {
+        (Some(_), Some(key)) => key,
+        _ => return Err(String::from("expected hosts, key type and key")),
+    };
+
+    let key = match BASE64.decode(key.as_bytes()) {
+        Ok(key) => key,
+        Err(error) => return Err(format!("invalid key: {}", error)),
+    };
+
+    Ok(Some(KnownHost {
+        hosts: parse_hosts(hosts)?,
+        key_hash: Sha256::digest(key.as_slice()).to_vec(),
+        revoked,
+    }))
+}
+
+fn parse_hosts(hosts: &str) -> Result<Hosts, String> {
+    let hashed = match hosts.strip_prefix(HASHED_HOST_PREFIX) {
+        Some(hashed) => hashed,
+        None => {
+            return Ok(Hosts::Patterns(
+                hosts.split(',').map(str::to_lowercase).collect(),
+            ))
+        }
+    };
+
+    let decoded = hashed.split_once('|').map(|(salt, hash)| {
+        (
+            BASE64.decode(salt.as_bytes()),
+            BASE64.decode(hash.as_bytes()),
+        )
+    });
+
+    match decoded {
+        Some((Ok(salt), Ok(hash))) => Ok(Hosts::Hashed { salt, hash }),
+        _ => Err(format!("invalid hashed host '{}'", hosts)),
+    }
+}
+
+/// Matches `text` against `pattern`, where `*` stands for any sequence of characters and `?` for
+/// any single character, ignoring case.
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+
+    let (mut pattern_index, mut text_index) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while text_index < text.len() {
+        match pattern.get(pattern_index) {
+            Some('*') => {
+                backtrack = Some((pattern_index, text_index));
+                pattern_index += 1;
+            }
+            Some(character) if *character == '?' || *character == text[text_index] => {
+                pattern_index += 1;
+                text_index += 1;
+            }
+            _ => match backtrack {
+                Some((star_index, star_text_index)) => {
+                    pattern_index = star_index + 1;
+                    text_index = star_text_index + 1;
+                    backtrack = Some((star_index, star_text_index + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[pattern_index..]
+        .iter()
+        .all(|character| *character == '*')
+}
+
+/// Port of an `ssh://` repository URL, if it names one. The scp-like syntax cannot.
+pub fn ssh_port(repository_url: &str) -> Option<u16> {
+    let authority = repository_url
+        .strip_prefix("ssh://")?
+        .split('/')
+        .next()
+        .unwrap_or_default();
+    let host = authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host)| host);
+
+    // The closing bracket of an IPv6 address comes before its port.
+    let port = host.rsplit_once(':')?.1;
+    if port.contains(']') {
+        return None;
+    }
+
+    port.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use data_encoding::HEXLOWER;
+
+    use super::*;
+
+    const KEY: &str = "AAAAC3NzaC1lZDI1NTE5AAAAIL8xBqutYZOf5qI3ilv6EHbn7MddpiDSdAHpk31kAHw7";
+    const KEY_SHA256: &str = "d549cceb373ae0d1860182fb31ec37f2ccada9a06239d26ef89df45bc5a91e98";
+    const OTHER_KEY: &str = "AAAAC3NzaC1lZDI1NTE5AAAAIJyMcEc+FruKQhlxL45olewyy8R/e8FJ6cR2ndyGTaWT";
+    const OTHER_KEY_SHA256: &str =
+        "bc8977d2e2ce9646464a9eadbb4bb2a293a751a740c1bd53ddb1d0b5e230da5e";
+
+    /// `github.com` and `[git.example.com]:2222`, as hashed by `ssh-keygen -H`.
+    const HASHED_GITHUB: &str = "|1|2/xmGOZRBATQseNYRRn69oQt49E=|IY6junOca5yuIFf90AsLYKiXJ3E=";
+    const HASHED_EXAMPLE_2222: &str =
+        "|1|EG7qhSox49uWeR2HXovZPtKxrAI=|xvA5HrJPn7JAPT5iDBPJpePYhoI=";
+
+    fn known_hosts(lines: &[String]) -> KnownHosts {
+        KnownHosts::parse("known_hosts", lines.join("\n").as_str()).unwrap()
+    }
+
+    fn key_hash(sha256: &str) -> Vec<u8> {
+        HEXLOWER.decode(sha256.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn matches_hashed_host() {
+        let known_hosts = known_hosts(&[format!("{} ssh-ed25519 {}", HASHED_GITHUB, KEY)]);
+
+        assert!(known_hosts
+            .verify("github.com", None, key_hash(KEY_SHA256).as_slice())
+            .is_ok());
+        assert!(known_hosts
+            .verify("GitHub.com", Some(22), key_hash(KEY_SHA256).as_slice())
+            .is_ok());
+        assert!(known_hosts
+            .verify("github.com", None, key_hash(OTHER_KEY_SHA256).as_slice())
+            .unwrap_err()
+            .contains("does not match"));
+        assert!(known_hosts
+            .verify("gitlab.com", None, key_hash(KEY_SHA256).as_slice())
+            .unwrap_err()
+            .contains("is not listed"));
+    }
+
+    #[test]
+    fn matches_non_default_port() {
+        let known_hosts = known_hosts(&[
+            format!("{} ssh-ed25519 {}", HASHED_EXAMPLE_2222, KEY),
+            format!("[git.example.org]:2222 ssh-ed25519 {}", KEY),
+        ]);
+
+        for host in ["git.example.com", "git.example.org"] {
+            assert!(known_hosts
+                .verify(host, Some(2222), key_hash(KEY_SHA256).as_slice())
+                .is_ok());
+            assert!(known_hosts
+                .verify(host, None, key_hash(KEY_SHA256).as_slice())
+                .is_err());
+            assert!(known_hosts
+                .verify(host, Some(2223), key_hash(KEY_SHA256).as_slice())
+                .is_err());
+        }
+    }
+
+    #[test]
+    fn negated_pattern_excludes_host() {
+        let known_hosts = known_hosts(&[format!(
+            "*.example.com,!evil.example.com ssh-ed25519 {}",
+            KEY
+        )]);
+
+        assert!(known_hosts
+            .verify("git.example.com", None, key_hash(KEY_SHA256).as_slice())
+            .is_ok());
+        assert!(known_hosts
+            .verify("evil.example.com", None, key_hash(KEY_SHA256).as_slice())
+            .unwrap_err()
+            .contains("is not listed"));
+    }
+
+    #[test]
+    fn revoked_key_is_refused() {
+        let known_hosts = known_hosts(&[
+            format!("@revoked * ssh-ed25519 {}", KEY),
+            format!("git.example.com ssh-ed25519 {}", KEY),
+            format!("git.example.com ssh-ed25519 {}", OTHER_KEY),
+        ]);
+
+        assert!(known_hosts
+            .verify("git.example.com", None, key_hash(KEY_SHA256).as_slice())
+            .unwrap_err()
+            .contains("is revoked"));
+        assert!(known_hosts
+            .verify(
+                "git.example.com",
+                None,
+                key_hash(OTHER_KEY_SHA256).as_slice()
+            )
+            .is_ok());
+    }
+
+    #[test]
+    fn skips_certificate_authorities_and_rejects_unknown_markers() {
+        let known_hosts = known_hosts(&[format!("@cert-authority * ssh-ed25519 {}", KEY)]);
+        assert!(known_hosts
+            .verify("git.example.com", None, key_hash(KEY_SHA256).as_slice())
+            .is_err());
+
+        assert!(KnownHosts::parse(
+            "known_hosts",
+            format!("@trusted * ssh-ed25519 {}", KEY).as_str()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn reads_port_of_ssh_urls() {
+        assert_eq!(
+            ssh_port("ssh://git@git.example.com:2222/repo.git"),
+            Some(2222)
+        );
+        assert_eq!(ssh_port("ssh://git@[::1]:2222/repo.git"), Some(2222));
+        assert_eq!(ssh_port("ssh://git@[::1]/repo.git"), None);
+        assert_eq!(ssh_port("ssh://git.example.com/repo.git"), None);
+        assert_eq!(ssh_port("git@git.example.com:repo.git"), None);
+    }
+}
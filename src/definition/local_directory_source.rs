@@ -0,0 +1,76 @@
+use std::path::Path;
+
+use crate::{
+    config::source_config::LocalDirectorySourceConfig,
+    error::{Error, ErrorKind},
+};
+
+use super::definition_source::DefinitionSource;
+
+/// File containing the version of the definitions, if none is configured.
+const VERSION_FILE_NAME: &str = "VERSION";
+
+/// Reads an existing directory as-is, which is handy for local development and CI.
+pub struct LocalDirectorySource {
+    path: String,
+    version: Option<String>,
+}
+
+impl LocalDirectorySource {
+    pub fn new(config: &LocalDirectorySourceConfig) -> LocalDirectorySource {
+        LocalDirectorySource {
+            path: config.path.clone(),
+            version: config.version.clone(),
+        }
+    }
+
+    fn ensure_directory_exists(&self) -> Result<(), Error> {
+        if Path::new(self.path.as_str()).is_dir() {
+            Ok(())
+        } else {
+            Err(Error::new(
+                ErrorKind::DefinitionsNotAvailable,
+                format!("definitions directory '{}' does not exist", self.path).as_str(),
+            ))
+        }
+    }
+}
+
+impl DefinitionSource for LocalDirectorySource {
+    fn fetch(&self) -> Result<(), Error> {
+        self.ensure_directory_exists()
+    }
+
+    /// There is nothing to download, the directory is read again as it is.
+    fn refresh(&self) -> Result<(), Error> {
+        self.ensure_directory_exists()
+    }
+
+    fn revision(&self) -> Result<String, Error> {
+        if let Some(version) = &self.version {
+            return Ok(version.clone());
+        }
+
+        let version_path = Path::new(self.path.as_str()).join(VERSION_FILE_NAME);
+        match std::fs::read_to_string(&version_path) {
+            Ok(version) if !version.trim().is_empty() => Ok(version.trim().to_string()),
+            Ok(_) => Err(Error::new(
+                ErrorKind::VersionReadFailure,
+                format!("version file '{}' is empty", version_path.display()).as_str(),
+            )),
+            Err(error) => Err(Error::new(
+                ErrorKind::VersionReadFailure,
+                format!(
+                    "failed to read version file '{}': {}",
+                    version_path.display(),
+                    error
+                )
+                .as_str(),
+            )),
+        }
+    }
+
+    fn directories(&self) -> Vec<String> {
+        vec![self.path.clone()]
+    }
+}
@@ -1,6 +1,20 @@
+pub mod archive_source;
+pub mod composite_definition_source;
+pub mod definition_snapshot;
+pub mod definition_source;
+pub mod definition_source_builder;
+pub mod directory_replacement;
+pub mod directory_watcher;
 pub mod downloader_async_wrapper;
 pub mod downloader_state;
 pub mod file_reader;
+pub mod git_credentials;
+pub mod git_definition_source;
+pub mod git_repository;
+pub mod known_hosts;
+pub mod local_directory_source;
+pub mod operation_guard;
 pub mod output_async_wrapper;
 pub mod rabbitmq_output;
 pub mod reader_state;
+pub mod signature_verifier;
@@ -0,0 +1,79 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, OnceLock,
+    },
+};
+
+use crate::error::{Error, ErrorKind};
+
+/// State of the operations on every directory guarded so far, by directory.
+static OPERATIONS: OnceLock<Mutex<HashMap<String, Arc<OperationState>>>> = OnceLock::new();
+
+#[derive(Default)]
+struct OperationState {
+    running: AtomicBool,
+    cancelled: AtomicBool,
+}
+
+/// Lets a single operation at a time run on a directory, across every source using it, so that
+/// the source built by a restarted pipeline never runs alongside an operation which the previous
+/// one left running after timing out.
+pub struct OperationGuard {
+    directory: String,
+    state: Arc<OperationState>,
+}
+
+impl OperationGuard {
+    pub fn new(directory: &str) -> OperationGuard {
+        let operations = OPERATIONS.get_or_init(|| Mutex::new(HashMap::new()));
+        let state = match operations.lock() {
+            Ok(mut operations) => operations.entry(directory.to_string()).or_default().clone(),
+            Err(_) => Arc::new(OperationState::default()),
+        };
+
+        OperationGuard {
+            directory: directory.to_string(),
+            state,
+        }
+    }
+
+    /// Marks an operation as running until the returned value is dropped, failing if another one
+    /// is still running.
+    pub fn begin(&self) -> Result<RunningOperation<'_>, Error> {
+        if self.state.running.swap(true, Ordering::SeqCst) {
+            return Err(Error::new(
+                ErrorKind::TaskFailure,
+                format!("another operation on '{}' is still running", self.directory).as_str(),
+            ));
+        }
+
+        self.state.cancelled.store(false, Ordering::SeqCst);
+
+        Ok(RunningOperation { state: &self.state })
+    }
+
+    /// Asks the running operation, if any, to stop as soon as it checks [`Self::cancelled`].
+    pub fn cancel(&self) {
+        if self.state.running.load(Ordering::SeqCst) {
+            self.state.cancelled.store(true, Ordering::SeqCst);
+        }
+    }
+
+    /// Flag set once the running operation is asked to stop.
+    pub fn cancelled(&self) -> &AtomicBool {
+        &self.state.cancelled
+    }
+}
+
+/// Operation started by [`OperationGuard::begin`], which ends when dropped.
+pub struct RunningOperation<'a> {
+    state: &'a OperationState,
+}
+
+impl Drop for RunningOperation<'_> {
+    fn drop(&mut self) {
+        self.state.running.store(false, Ordering::SeqCst);
+    }
+}
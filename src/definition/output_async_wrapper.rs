@@ -1,87 +1,204 @@
-use std::time::Duration;
-
-use async_recursion::async_recursion;
 use cooplan_definitions_lib::definition::Definition;
-use tokio::time::sleep;
+use tokio::{sync::watch::Receiver, time::sleep};
 
-use crate::config::output_config::OutputConfig;
+use crate::{
+    config::{degraded_mode_config::DegradedModeConfig, output_config::OutputConfig},
+    error::Error,
+    retry_policy::{degrade, RetryPolicy},
+    status::status_reporter::{Stage, StatusReporter},
+};
 
-use super::rabbitmq_output::RabbitMQOutput;
+use super::{rabbitmq_output::RabbitMQOutput, reader_state::ReaderState};
 
 pub struct OutputAsyncWrapper {
-    config: OutputConfig,
+    config_receiver: Receiver<OutputConfig>,
+    degraded_mode_config: DegradedModeConfig,
     output: RabbitMQOutput,
-
-    connect_retry_count: i32,
-    set_retry_count: i32,
+    status_reporter: StatusReporter,
 }
 
 impl OutputAsyncWrapper {
-    pub fn new(config: OutputConfig, output: RabbitMQOutput) -> OutputAsyncWrapper {
+    pub fn new(
+        config_receiver: Receiver<OutputConfig>,
+        degraded_mode_config: DegradedModeConfig,
+        output: RabbitMQOutput,
+        status_reporter: StatusReporter,
+    ) -> OutputAsyncWrapper {
         OutputAsyncWrapper {
-            config,
+            config_receiver,
+            degraded_mode_config,
             output,
-
-            connect_retry_count: 0,
-            set_retry_count: 0,
+            status_reporter,
         }
     }
 
-    #[async_recursion]
-    pub async fn try_connect(&mut self) {
-        match self.output.connect().await {
-            Ok(_) => {
-                self.connect_retry_count = 0;
-                log::info!("sucessfully connected to output");
-            }
-            Err(error) => {
-                log::warn!("failed to connect to output: {}", error);
-                if self.connect_retry_count >= self.config.connection_retry_count {
-                    std::process::exit(1);
-                }
-
-                sleep(Duration::from_secs(
-                    self.config.connection_retry_interval_seconds,
-                ))
-                .await;
+    /// Latest configuration, which may change while running.
+    fn config(&self) -> OutputConfig {
+        self.config_receiver.borrow().clone()
+    }
 
-                self.connect_retry_count += 1;
-                log::warn!(
-                    "retrying to connect to output, count: {}",
-                    self.connect_retry_count
-                );
+    /// Publishes every definition made available by the reader, reconnecting whenever the
+    /// AMQP channel changes, until `shutdown_receiver` signals the shutdown.
+    ///
+    /// Returns an error if publishing cannot be completed and the degraded mode is configured to exit.
+    pub async fn run(
+        &mut self,
+        mut reader_state_receiver: Receiver<ReaderState>,
+        mut shutdown_receiver: Receiver<bool>,
+    ) -> Result<(), Error> {
+        let result = self
+            .publish(&mut reader_state_receiver, &mut shutdown_receiver)
+            .await;
+
+        self.output.close().await;
+
+        result
+    }
 
-                self.try_connect().await;
+    async fn publish(
+        &mut self,
+        reader_state_receiver: &mut Receiver<ReaderState>,
+        shutdown_receiver: &mut Receiver<bool>,
+    ) -> Result<(), Error> {
+        self.try_connect().await?;
+        self.try_set_available(reader_state_receiver).await?;
+
+        let mut config_open = true;
+
+        loop {
+            tokio::select! {
+                result = reader_state_receiver.changed() => {
+                    if result.is_err() {
+                        log::warn!("reader state sender has been dropped");
+                        return Ok(());
+                    }
+
+                    self.try_set_available(reader_state_receiver).await?;
+                }
+                result = self.config_receiver.changed(), if config_open => {
+                    if result.is_err() {
+                        config_open = false;
+                        continue;
+                    }
+
+                    let amqp_channel_name = self.config().amqp_channel_name;
+                    if amqp_channel_name != self.output.amqp_channel_name() {
+                        log::info!("switching output to AMQP channel '{}'", amqp_channel_name);
+                        self.output.close().await;
+                        self.output.set_amqp_channel_name(amqp_channel_name);
+
+                        self.try_connect().await?;
+                        self.try_set_available(reader_state_receiver).await?;
+                    } else {
+                        log::info!("applying new output config");
+                    }
+                }
+                _ = shutdown_receiver.changed() => {
+                    log::info!("shutting down output");
+                    return Ok(());
+                }
             }
         }
     }
 
-    #[async_recursion]
-    pub async fn try_set(&mut self, definition: Definition) {
-        match self.output.set(&definition).await {
-            Ok(_) => {
-                self.set_retry_count = 0;
-                log::info!("sucessfully set new definition on output");
+    async fn try_set_available(
+        &mut self,
+        reader_state_receiver: &Receiver<ReaderState>,
+    ) -> Result<(), Error> {
+        if !reader_state_receiver.borrow().available {
+            return Ok(());
+        }
+
+        let optional_definition = reader_state_receiver.borrow().definition();
+        match optional_definition {
+            Some(definition) => self.try_set(definition).await,
+            None => {
+                log::warn!("reader state is available, but definition is none");
+                Ok(())
             }
-            Err(error) => {
-                log::warn!("failed to set definition on output: {}", error);
-                if self.set_retry_count >= self.config.set_retry_count {
-                    std::process::exit(1);
-                }
+        }
+    }
+
+    pub async fn try_connect(&mut self) -> Result<(), Error> {
+        let mut retry = RetryPolicy::new(self.config().connection_retry).start();
 
-                sleep(Duration::from_secs(self.config.set_retry_interval_seconds)).await;
+        loop {
+            match self.output.connect().await {
+                Ok(_) => {
+                    log::info!("sucessfully connected to output");
+                    self.status_reporter.set_healthy(Stage::Output);
 
-                if !self.output.is_connected() {
-                    self.try_connect().await;
+                    return Ok(());
+                }
+                Err(error) => {
+                    log::warn!("failed to connect to output: {}", error);
+
+                    let delay = match retry.next_delay() {
+                        Some(delay) => {
+                            log::warn!(
+                                "retrying to connect to output in {:?}, count: {}",
+                                delay,
+                                retry.retries()
+                            );
+                            delay
+                        }
+                        None => {
+                            let message = format!("failed to connect to output: {}", error);
+                            degrade(
+                                &self.degraded_mode_config,
+                                &self.status_reporter,
+                                Stage::Output,
+                                message.as_str(),
+                            )?
+                        }
+                    };
+
+                    sleep(delay).await;
                 }
+            }
+        }
+    }
 
-                self.set_retry_count += 1;
-                log::warn!(
-                    "retrying to set definition on output, count: {}",
-                    self.set_retry_count
-                );
+    pub async fn try_set(&mut self, definition: Definition) -> Result<(), Error> {
+        let mut retry = RetryPolicy::new(self.config().set_retry).start();
 
-                self.try_set(definition).await;
+        loop {
+            match self.output.set(&definition).await {
+                Ok(_) => {
+                    log::info!("sucessfully set new definition on output");
+                    self.status_reporter.set_healthy(Stage::Output);
+
+                    return Ok(());
+                }
+                Err(error) => {
+                    log::warn!("failed to set definition on output: {}", error);
+
+                    let delay = match retry.next_delay() {
+                        Some(delay) => {
+                            log::warn!(
+                                "retrying to set definition on output in {:?}, count: {}",
+                                delay,
+                                retry.retries()
+                            );
+                            delay
+                        }
+                        None => {
+                            let message = format!("failed to set definition on output: {}", error);
+                            degrade(
+                                &self.degraded_mode_config,
+                                &self.status_reporter,
+                                Stage::Output,
+                                message.as_str(),
+                            )?
+                        }
+                    };
+
+                    sleep(delay).await;
+
+                    if !self.output.is_connected() {
+                        self.try_connect().await?;
+                    }
+                }
             }
         }
     }
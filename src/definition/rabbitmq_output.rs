@@ -13,6 +13,7 @@ pub struct RabbitMQOutput {
     connection_uri: String,
     connected: bool,
     amqp_channel_name: String,
+    connection: Option<Connection>,
     channel: Option<Channel>,
 }
 
@@ -22,6 +23,7 @@ impl RabbitMQOutput {
             connection_uri,
             connected: false,
             amqp_channel_name,
+            connection: None,
             channel: None,
         }
     }
@@ -34,10 +36,12 @@ impl RabbitMQOutput {
         match Connection::connect(self.connection_uri.as_str(), connection_options).await {
             Ok(connection) => match connection.create_channel().await {
                 Ok(channel) => {
-                    let mut options = QueueDeclareOptions::default();
-                    options.durable = true;
-                    options.exclusive = false;
-                    options.auto_delete = false;
+                    let options = QueueDeclareOptions {
+                        durable: true,
+                        exclusive: false,
+                        auto_delete: false,
+                        ..Default::default()
+                    };
 
                     let mut map: BTreeMap<ShortString, AMQPValue> = BTreeMap::new();
                     map.insert(
@@ -53,6 +57,7 @@ impl RabbitMQOutput {
                     {
                         Ok(_) => {
                             self.connected = true;
+                            self.connection = Some(connection);
                             self.channel = Some(channel);
 
                             Ok(())
@@ -108,6 +113,39 @@ impl RabbitMQOutput {
         }
     }
 
+    pub fn amqp_channel_name(&self) -> &str {
+        self.amqp_channel_name.as_str()
+    }
+
+    /// Switches to another AMQP channel, requiring a new connection.
+    pub fn set_amqp_channel_name(&mut self, amqp_channel_name: String) {
+        self.amqp_channel_name = amqp_channel_name;
+        self.connected = false;
+        self.connection = None;
+        self.channel = None;
+    }
+
+    /// Closes the channel and the connection, if open.
+    pub async fn close(&mut self) {
+        if let Some(channel) = self.channel.take() {
+            if channel.status().connected() {
+                if let Err(error) = channel.close(200, "closing").await {
+                    log::warn!("failed to close AMQP channel: {}", error);
+                }
+            }
+        }
+
+        if let Some(connection) = self.connection.take() {
+            if connection.status().connected() {
+                if let Err(error) = connection.close(200, "closing").await {
+                    log::warn!("failed to close AMQP connection: {}", error);
+                }
+            }
+        }
+
+        self.connected = false;
+    }
+
     pub fn is_connected(&self) -> bool {
         match &self.channel {
             Some(channel) => channel.status().connected(),
@@ -12,7 +12,7 @@ pub struct ReaderState {
 impl ReaderState {
     pub fn new(available: bool, definition: Definition) -> ReaderState {
         ReaderState {
-            available: available,
+            available,
             definition: Some(definition),
             last_updated: Instant::now(),
         }
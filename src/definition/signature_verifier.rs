@@ -0,0 +1,248 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use rand::Rng;
+
+use crate::{
+    config::signature_verification_config::{SignatureFormat, SignatureVerificationConfig},
+    error::{Error, ErrorKind},
+};
+
+/// Namespace git uses for SSH signatures.
+const SSH_SIGNATURE_NAMESPACE: &str = "git";
+
+/// Times a signature file is created under another random name if the name is already taken.
+const SIGNATURE_FILE_ATTEMPTS: usize = 16;
+
+/// Verifies detached signatures against the trusted keys of a keyring, through `gpgv` or
+/// `ssh-keygen`.
+pub struct SignatureVerifier {
+    format: SignatureFormat,
+    keyring: PathBuf,
+}
+
+impl SignatureVerifier {
+    pub fn new(config: &SignatureVerificationConfig) -> SignatureVerifier {
+        // gpgv looks keyrings without a directory up within its home directory.
+        let keyring = match Path::new(config.keyring.as_str()).canonicalize() {
+            Ok(keyring) => keyring,
+            Err(_) => PathBuf::from(config.keyring.as_str()),
+        };
+
+        SignatureVerifier {
+            format: config.format,
+            keyring,
+        }
+    }
+
+    /// Checks that `signature` signs `data` with one of the trusted keys.
+    pub fn verify(&self, signature: &[u8], data: &[u8]) -> Result<(), Error> {
+        let signature_file = SignatureFile::write(signature)?;
+
+        match self.format {
+            SignatureFormat::Gpg => self.verify_gpg(signature_file.path(), data),
+            SignatureFormat::Ssh => self.verify_ssh(signature_file.path(), data),
+        }
+    }
+
+    fn verify_gpg(&self, signature_path: &Path, data: &[u8]) -> Result<(), Error> {
+        let mut command = Command::new("gpgv");
+        command
+            .arg("--status-fd")
+            .arg("1")
+            .arg("--keyring")
+            .arg(&self.keyring)
+            .arg(signature_path)
+            .arg("-");
+
+        let output = run(command, data)?;
+        let status = String::from_utf8_lossy(&output.stdout);
+
+        if output.success
+            && status
+                .lines()
+                .any(|line| line.starts_with("[GNUPG:] GOODSIG"))
+        {
+            Ok(())
+        } else {
+            Err(untrusted(output.stderr.as_str()))
+        }
+    }
+
+    fn verify_ssh(&self, signature_path: &Path, data: &[u8]) -> Result<(), Error> {
+        let mut find_principals = Command::new("ssh-keygen");
+        find_principals
+            .arg("-Y")
+            .arg("find-principals")
+            .arg("-f")
+            .arg(&self.keyring)
+            .arg("-s")
+            .arg(signature_path);
+
+        let output = run(find_principals, &[])?;
+        if !output.success {
+            return Err(untrusted(output.stderr.as_str()));
+        }
+
+        let principals = String::from_utf8_lossy(&output.stdout).to_string();
+        let principal = match principals
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+        {
+            Some(principal) => principal,
+            None => {
+                return Err(untrusted(
+                    "no principal of the allowed signers made the signature",
+                ))
+            }
+        };
+
+        let mut verify = Command::new("ssh-keygen");
+        verify
+            .arg("-Y")
+            .arg("verify")
+            .arg("-f")
+            .arg(&self.keyring)
+            .arg("-I")
+            .arg(principal)
+            .arg("-n")
+            .arg(SSH_SIGNATURE_NAMESPACE)
+            .arg("-s")
+            .arg(signature_path);
+
+        let output = run(verify, data)?;
+        if output.success {
+            Ok(())
+        } else {
+            Err(untrusted(output.stderr.as_str()))
+        }
+    }
+}
+
+struct CommandOutput {
+    success: bool,
+    stdout: Vec<u8>,
+    stderr: String,
+}
+
+fn run(mut command: Command, input: &[u8]) -> Result<CommandOutput, Error> {
+    let program = command.get_program().to_string_lossy().to_string();
+
+    let mut child = match command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(error) => {
+            return Err(Error::new(
+                ErrorKind::SignatureVerificationFailure,
+                format!("failed to run '{}': {}", program, error).as_str(),
+            ))
+        }
+    };
+
+    if let Some(mut stdin) = child.stdin.take() {
+        // The command may exit without reading its input, such as when the key is unknown.
+        let _ = stdin.write_all(input);
+    }
+
+    match child.wait_with_output() {
+        Ok(output) => Ok(CommandOutput {
+            success: output.status.success(),
+            stdout: output.stdout,
+            stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        }),
+        Err(error) => Err(Error::new(
+            ErrorKind::SignatureVerificationFailure,
+            format!("failed to run '{}': {}", program, error).as_str(),
+        )),
+    }
+}
+
+fn untrusted(reason: &str) -> Error {
+    Error::new(
+        ErrorKind::SignatureVerificationFailure,
+        format!("signature is not made by a trusted key: {}", reason).as_str(),
+    )
+}
+
+/// Signature written to a temporary file, removed once dropped.
+struct SignatureFile {
+    path: PathBuf,
+}
+
+impl SignatureFile {
+    /// Writes `signature` into a new file of the temporary directory, readable by the current user
+    /// only. The file is never one which already exists, such as a link planted by another user.
+    fn write(signature: &[u8]) -> Result<SignatureFile, Error> {
+        let mut attempts = 0;
+
+        loop {
+            let path = std::env::temp_dir().join(format!(
+                "cooplan-signature-{}-{:016x}",
+                std::process::id(),
+                rand::thread_rng().gen::<u64>()
+            ));
+
+            let error = match create_private(path.as_path()) {
+                Ok(mut file) => match file.write_all(signature) {
+                    Ok(_) => return Ok(SignatureFile { path }),
+                    Err(error) => {
+                        let _ = std::fs::remove_file(&path);
+                        error
+                    }
+                },
+                Err(error)
+                    if error.kind() == std::io::ErrorKind::AlreadyExists
+                        && attempts < SIGNATURE_FILE_ATTEMPTS =>
+                {
+                    attempts += 1;
+                    continue;
+                }
+                Err(error) => error,
+            };
+
+            return Err(Error::new(
+                ErrorKind::SignatureVerificationFailure,
+                format!(
+                    "failed to write signature to '{}': {}",
+                    path.display(),
+                    error
+                )
+                .as_str(),
+            ));
+        }
+    }
+
+    fn path(&self) -> &Path {
+        self.path.as_path()
+    }
+}
+
+/// Creates `path`, failing if it already exists, with permissions for the current user only.
+fn create_private(path: &Path) -> Result<File, std::io::Error> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+
+        options.mode(0o600);
+    }
+
+    options.open(path)
+}
+
+impl Drop for SignatureFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
@@ -13,6 +13,16 @@ pub enum ErrorKind {
     ChannelNotAvailable,
     DataWritingFailure,
     VersionReadFailure,
+    VersionSetFailure,
+    RetriesExhausted,
+    TaskFailure,
+    WatchFailure,
+    FailedToUnpackArchive,
+    SignatureVerificationFailure,
+    AuthenticationFailure,
+    HistoryRewritten,
+    RepositoryCorrupted,
+    OperationCancelled,
 }
 
 #[derive(Debug)]
@@ -39,3 +49,30 @@ impl fmt::Display for Error {
         write!(f, "{}", self.message)
     }
 }
+
+impl std::error::Error for Error {}
+
+impl From<cooplan_definition_git_downloader::error::Error> for Error {
+    fn from(error: cooplan_definition_git_downloader::error::Error) -> Self {
+        use cooplan_definition_git_downloader::error::ErrorKind as DownloaderErrorKind;
+
+        let kind = match error.kind() {
+            DownloaderErrorKind::FailedToCloneRepository => ErrorKind::FailedToCloneRepository,
+            DownloaderErrorKind::FailedToOpenRepository => ErrorKind::FailedToOpenRepository,
+            DownloaderErrorKind::FailedToUpdateDefinitions => ErrorKind::FailedToUpdateDefinitions,
+            DownloaderErrorKind::FailedToReadEnvironmentVariables => {
+                ErrorKind::FailedToReadEnvironmentVariables
+            }
+            DownloaderErrorKind::SourceMissingId => ErrorKind::SourceMissingId,
+            DownloaderErrorKind::DefinitionsNotAvailable => ErrorKind::DefinitionsNotAvailable,
+            DownloaderErrorKind::SerializationFailure => ErrorKind::SerializationFailure,
+            DownloaderErrorKind::ConnectionFailure => ErrorKind::ConnectionFailure,
+            DownloaderErrorKind::ChannelNotAvailable => ErrorKind::ChannelNotAvailable,
+            DownloaderErrorKind::VersionSetFailure => ErrorKind::VersionSetFailure,
+            DownloaderErrorKind::DataWritingFailure => ErrorKind::DataWritingFailure,
+            DownloaderErrorKind::VersionReadFailure => ErrorKind::VersionReadFailure,
+        };
+
+        Error::new(kind, error.message.as_str())
+    }
+}
@@ -1,74 +1,192 @@
+pub mod cli;
 pub mod config;
 pub mod definition;
 pub mod error;
+pub mod pipeline;
+pub mod redacting_logger;
+pub mod retry_policy;
+pub mod secret;
+pub mod status;
+pub mod update_schedule;
 
-use std::io::{Error, ErrorKind};
+use std::{
+    io::{Error, ErrorKind},
+    process::ExitCode,
+};
 
-use cooplan_definition_git_downloader::downloader::Downloader;
-use cooplan_definition_git_downloader::version_detector::VersionDetector;
-use definition::downloader_state::DownloaderState;
-use definition::file_reader::FileReader;
-use definition::output_async_wrapper::OutputAsyncWrapper;
-use definition::reader_state::ReaderState;
+use clap::Parser;
+use cli::{Cli, Command, ConfigCommand};
+use config::{
+    config::Config, config_format::ConfigFormat, config_reader::ConfigReader, config_schema,
+    config_validator, config_watcher::ConfigWatcher, git_config::GitSources,
+    source_config::SourceConfig,
+};
 use definition::{
-    downloader_async_wrapper::DownloaderAsyncWrapper, rabbitmq_output::RabbitMQOutput,
+    definition_snapshot::DefinitionSnapshot, definition_source_builder,
+    file_reader::CATEGORIES_PATH,
 };
-use tokio::{sync::watch, task};
+use log::LevelFilter;
+use pipeline::Pipeline;
+use redacting_logger::RedactingLogger;
+use status::{status_reporter::StatusReporter, status_server::StatusServer};
+use tokio::sync::watch;
 
 #[tokio::main]
-async fn main() -> Result<(), Error> {
-    match simple_logger::SimpleLogger::new().env().init() {
-        Ok(_) => (),
-        Err(error) => {
-            return Err(Error::new(
-                ErrorKind::Interrupted,
-                format!("failed to initialize logger: {}", error),
-            ));
-        }
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    let mut logger = simple_logger::SimpleLogger::new().env();
+
+    if let Some(log_level) = cli.log_level {
+        logger = logger.with_level(log_level);
     }
 
-    match run_definition_downloader().await {
-        Ok(_) => (),
+    // The wrapped logger filters by level itself.
+    if let Err(error) = log::set_boxed_logger(Box::new(RedactingLogger::new(logger))) {
+        eprintln!("failed to initialize logger: {}", error);
+        return ExitCode::FAILURE;
+    }
+    log::set_max_level(LevelFilter::Trace);
+
+    let result = match cli.command {
+        Some(Command::ValidateConfig) => validate_config(&cli),
+        Some(Command::Config {
+            command: ConfigCommand::Show { format },
+        }) => show_config(&cli, format),
+        Some(Command::Config {
+            command: ConfigCommand::Schema,
+        }) => show_config_schema(),
+        None => run_definition_downloader(&cli).await,
+    };
+
+    let exit_code = match result {
+        Ok(_) => ExitCode::SUCCESS,
         Err(error) => {
             log::error!("{}", error);
+            ExitCode::FAILURE
         }
+    };
+
+    log::logger().flush();
+
+    exit_code
+}
+
+fn build_config_reader(cli: &Cli) -> Box<dyn ConfigReader> {
+    crate::config::config_reader_builder::build(
+        cli.config.as_str(),
+        cli.config_format,
+        cli.profile.as_deref(),
+        cli.overrides.clone(),
+    )
+}
+
+/// Reads the layered configuration, registering the credentials it contains as secrets.
+fn read_unvalidated_config(cli: &Cli) -> Result<Config, Error> {
+    let config = build_config_reader(cli).read()?;
+    config.register_secrets();
+
+    Ok(config)
+}
+
+/// Reads the layered configuration and validates it.
+fn read_config(cli: &Cli) -> Result<Config, Error> {
+    let config = read_unvalidated_config(cli)?;
+
+    match config_validator::validate(&config, CATEGORIES_PATH) {
+        Ok(_) => Ok(config),
+        Err(error) => Err(Error::new(ErrorKind::InvalidData, error.to_string())),
     }
+}
+
+fn validate_config(cli: &Cli) -> Result<(), Error> {
+    read_config(cli)?;
+    log::info!("config is valid");
 
     Ok(())
 }
 
-async fn run_definition_downloader() -> Result<(), Error> {
-    let config = match crate::config::config_reader_builder::default().read() {
-        Ok(config) => config,
-        Err(error) => {
-            return Err(error);
+/// Serializes `config` with its credentials redacted.
+fn effective_config(config: &Config, format: ConfigFormat) -> Result<String, Error> {
+    match format.serialize(config) {
+        Ok(serialized_config) => Ok(secret::redact(serialized_config.as_str())),
+        Err(error) => Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("failed to serialize config as {}: {}", format, error),
+        )),
+    }
+}
+
+/// Directories the definitions are read from.
+fn definitions_path(config: &Config) -> String {
+    match config.source() {
+        SourceConfig::Git => match config.git() {
+            Some(GitSources::Multiple(git_configs)) => git_configs
+                .iter()
+                .map(|git_config| git_config.repository_local_dir.clone())
+                .collect::<Vec<String>>()
+                .join(", "),
+            _ => String::from(CATEGORIES_PATH),
+        },
+        SourceConfig::LocalDirectory(local_directory) => local_directory.path,
+        SourceConfig::Archive(archive) => archive.staging_dir,
+    }
+}
+
+/// AMQP connection URI with its credentials redacted.
+fn amqp_target() -> String {
+    match secret::read_env("AMQP_CONNECTION_URI") {
+        Ok(connection_uri) => {
+            secret::register_uri_password(connection_uri.as_str());
+            secret::redact(connection_uri.as_str())
         }
-    };
+        Err(_) => String::from("not configured"),
+    }
+}
 
-    let definition_downloader_state = DownloaderState::new(false);
+fn show_config(cli: &Cli, format: Option<ConfigFormat>) -> Result<(), Error> {
+    let config = read_unvalidated_config(cli)?;
 
-    let (downloader_state_sender, mut downloader_state_receiver) =
-        watch::channel(definition_downloader_state);
+    if let Err(error) = config_validator::validate(&config, CATEGORIES_PATH) {
+        log::warn!("{}", error);
+    }
 
-    let definition_reader_state = ReaderState::new_not_available();
-    let (reader_state_sender, mut reader_state_receiver) = watch::channel(definition_reader_state);
+    let format = match format.or(cli.config_format) {
+        Some(format) => format,
+        None => ConfigFormat::from_path(cli.config.as_str()).unwrap_or(ConfigFormat::Json),
+    };
 
-    let version_detector_repository_local_dir = config.git().repository_local_dir;
-    tokio::spawn(async move {
-        let version_detector = VersionDetector::new(version_detector_repository_local_dir);
+    // Runtime values which are not part of the configuration go to the log, which is written to
+    // stderr, keeping the output usable as a configuration file.
+    log::info!("effective categories path: {}", definitions_path(&config));
+    log::info!("effective AMQP target: {}", amqp_target());
 
-        let mut reader = FileReader::new(
-            String::from("./categories/"),
-            reader_state_sender,
-            downloader_state_receiver,
-            version_detector,
-        );
+    println!("{}", effective_config(&config, format)?);
 
-        reader.run().await;
-    });
+    Ok(())
+}
+
+fn show_config_schema() -> Result<(), Error> {
+    match serde_json::to_string_pretty(config_schema::schema()) {
+        Ok(schema) => {
+            println!("{}", schema);
+            Ok(())
+        }
+        Err(error) => Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("failed to serialize config schema: {}", error),
+        )),
+    }
+}
+
+async fn run_definition_downloader(cli: &Cli) -> Result<(), Error> {
+    let mut config = read_config(cli)?;
 
-    let connection_uri = match std::env::var("AMQP_CONNECTION_URI") {
-        Ok(connection_uri) => connection_uri,
+    let connection_uri = match secret::read_env("AMQP_CONNECTION_URI") {
+        Ok(connection_uri) => {
+            secret::register_uri_password(connection_uri.as_str());
+            connection_uri
+        }
         Err(error) => {
             return Err(Error::new(
                 ErrorKind::InvalidData,
@@ -77,52 +195,107 @@ async fn run_definition_downloader() -> Result<(), Error> {
         }
     };
 
-    let output_config = config.output();
+    if log::log_enabled!(log::Level::Debug) {
+        log::debug!(
+            "effective config: {}",
+            effective_config(&config, ConfigFormat::Json)?
+        );
+        log::debug!("effective categories path: {}", definitions_path(&config));
+        log::debug!(
+            "effective AMQP target: {}",
+            secret::redact(connection_uri.as_str())
+        );
+    }
+
+    if let Err(error) = DefinitionSnapshot::clear() {
+        return Err(Error::other(error));
+    }
+
+    let (config_sender, mut config_receiver) = watch::channel(config.clone());
+    let config_watcher = ConfigWatcher::new(
+        build_config_reader(cli),
+        cli.config.as_str(),
+        CATEGORIES_PATH,
+        config_sender,
+    );
 
     tokio::spawn(async move {
-        let mut output =
-            RabbitMQOutput::new(connection_uri, output_config.amqp_channel_name.clone());
+        if let Err(error) = config_watcher.run().await {
+            log::error!("config hot reload is disabled: {}", error);
+        }
+    });
 
-        let mut output_wrapper = OutputAsyncWrapper::new(output_config, output);
+    let status_reporter = StatusReporter::new();
 
-        output_wrapper.try_connect().await;
+    if let Some(listen_address) = config.status().listen_address {
+        let status_server = StatusServer::new(listen_address.as_str(), status_reporter.clone());
 
-        if reader_state_receiver.borrow().available {
-            let optional_definition = reader_state_receiver.borrow().definition();
-            match optional_definition {
-                Some(definition) => output_wrapper.try_set(definition).await,
-                None => log::warn!("reader state is available, but definition is none"),
+        tokio::spawn(async move {
+            if let Err(error) = status_server.run().await {
+                log::error!("status server stopped: {}", error);
             }
-        }
+        });
+    }
+
+    loop {
+        let (definition_downloader_config_sender, definition_downloader_config_receiver) =
+            watch::channel(config.definition_downloader());
+        let (output_config_sender, output_config_receiver) = watch::channel(config.output());
+
+        let source = match definition_source_builder::build(&config) {
+            Ok(source) => source,
+            Err(error) => return Err(Error::other(error)),
+        };
+
+        let mut pipeline = Pipeline::start(
+            source,
+            connection_uri.clone(),
+            definition_downloader_config_receiver,
+            output_config_receiver,
+            config.degraded_mode(),
+            status_reporter.clone(),
+        );
+
+        let mut config_open = true;
 
         loop {
-            reader_state_receiver.changed().await;
+            tokio::select! {
+                result = pipeline.wait() => {
+                    pipeline.shutdown().await;
 
-            if reader_state_receiver.borrow().available {
-                let optional_definition = reader_state_receiver.borrow().definition();
-                match optional_definition {
-                    Some(definition) => output_wrapper.try_set(definition).await,
-                    None => log::warn!("reader state is available, but definition is none"),
+                    return match result {
+                        Ok(_) => Ok(()),
+                        Err(error) => Err(Error::other(error)),
+                    };
                 }
-            }
-        }
-    });
+                result = config_receiver.changed(), if config_open => {
+                    if result.is_err() {
+                        // The watcher is gone, so the config cannot change anymore.
+                        config_open = false;
+                        continue;
+                    }
 
-    let definition_downloader_config = config.definition_downloader();
-    let git_config = config.git();
+                    let new_config = config_receiver.borrow_and_update().clone();
 
-    let download = task::spawn(async move {
-        let definition_git_downloader = Downloader::new(git_config);
-        let mut definition_wrapper = DownloaderAsyncWrapper::new(
-            definition_git_downloader,
-            definition_downloader_config,
-            downloader_state_sender,
-        );
+                    if new_config.status() != config.status() {
+                        log::warn!("status config changed, restart the provider to apply it");
+                    }
 
-        definition_wrapper.run().await;
-    });
+                    if config.requires_restart(&new_config) {
+                        log::info!(
+                            "source, git or degraded mode config changed, restarting the pipeline"
+                        );
+                        pipeline.shutdown().await;
+                        config = new_config;
+                        break;
+                    }
 
-    download.await;
-
-    Ok(())
+                    definition_downloader_config_sender
+                        .send_replace(new_config.definition_downloader());
+                    output_config_sender.send_replace(new_config.output());
+                    config = new_config;
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,149 @@
+use std::{sync::Arc, time::Duration};
+
+use tokio::{
+    sync::watch::{self, Receiver, Sender},
+    task::{JoinError, JoinHandle},
+    time::timeout,
+};
+
+use crate::{
+    config::{
+        definition_downloader_config::DefinitionDownloaderConfig,
+        degraded_mode_config::DegradedModeConfig, output_config::OutputConfig,
+    },
+    definition::{
+        definition_source::DefinitionSource, downloader_async_wrapper::DownloaderAsyncWrapper,
+        downloader_state::DownloaderState, file_reader::FileReader,
+        output_async_wrapper::OutputAsyncWrapper, rabbitmq_output::RabbitMQOutput,
+        reader_state::ReaderState,
+    },
+    error::{Error, ErrorKind},
+    status::status_reporter::StatusReporter,
+};
+
+/// Time given to the output to close its connection before being aborted.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Running downloader, reader and output tasks.
+pub struct Pipeline {
+    source: Arc<dyn DefinitionSource>,
+    download: JoinHandle<Result<(), Error>>,
+    reader: JoinHandle<()>,
+    output: JoinHandle<Result<(), Error>>,
+    shutdown_sender: Sender<bool>,
+}
+
+impl Pipeline {
+    pub fn start(
+        source: Arc<dyn DefinitionSource>,
+        connection_uri: String,
+        definition_downloader_config_receiver: Receiver<DefinitionDownloaderConfig>,
+        output_config_receiver: Receiver<OutputConfig>,
+        degraded_mode_config: DegradedModeConfig,
+        status_reporter: StatusReporter,
+    ) -> Pipeline {
+        let definition_downloader_state = DownloaderState::new_not_available();
+
+        let (downloader_state_sender, downloader_state_receiver) =
+            watch::channel(definition_downloader_state);
+
+        let definition_reader_state = ReaderState::new_not_available();
+        let (reader_state_sender, reader_state_receiver) = watch::channel(definition_reader_state);
+
+        let (shutdown_sender, shutdown_receiver) = watch::channel(false);
+
+        let reader = tokio::spawn(async move {
+            let mut reader = FileReader::new(reader_state_sender, downloader_state_receiver);
+
+            reader.run().await;
+        });
+
+        let output_degraded_mode_config = degraded_mode_config.clone();
+        let output_status_reporter = status_reporter.clone();
+        let output = tokio::spawn(async move {
+            let amqp_channel_name = output_config_receiver.borrow().amqp_channel_name.clone();
+            let output = RabbitMQOutput::new(connection_uri, amqp_channel_name);
+
+            let mut output_wrapper = OutputAsyncWrapper::new(
+                output_config_receiver,
+                output_degraded_mode_config,
+                output,
+                output_status_reporter,
+            );
+
+            output_wrapper
+                .run(reader_state_receiver, shutdown_receiver)
+                .await
+        });
+
+        let download_source = source.clone();
+        let download = tokio::spawn(async move {
+            let mut definition_wrapper = DownloaderAsyncWrapper::new(
+                download_source,
+                definition_downloader_config_receiver,
+                degraded_mode_config,
+                downloader_state_sender,
+                status_reporter,
+            );
+
+            definition_wrapper.run().await
+        });
+
+        Pipeline {
+            source,
+            download,
+            reader,
+            output,
+            shutdown_sender,
+        }
+    }
+
+    /// Waits for the downloader or the output to stop, which only happens on failure.
+    pub async fn wait(&mut self) -> Result<(), Error> {
+        let result = tokio::select! {
+            result = &mut self.download => result,
+            result = &mut self.output => result,
+        };
+
+        flatten(result)
+    }
+
+    /// Stops every task, giving the output the chance to close its connection.
+    ///
+    /// A source operation running on the blocking pool cannot be aborted, so it is cancelled
+    /// instead.
+    pub async fn shutdown(self) {
+        self.download.abort();
+        self.source.cancel();
+        self.reader.abort();
+
+        self.shutdown_sender.send_replace(true);
+
+        let mut output = self.output;
+        if output.is_finished() {
+            return;
+        }
+
+        match timeout(SHUTDOWN_TIMEOUT, &mut output).await {
+            Ok(result) => {
+                if let Err(error) = flatten(result) {
+                    log::warn!("output failed while shutting down: {}", error);
+                }
+            }
+            Err(_) => {
+                log::warn!("output did not shut down in time, aborting it");
+                output.abort();
+            }
+        }
+    }
+}
+
+fn flatten(result: Result<Result<(), Error>, JoinError>) -> Result<(), Error> {
+    match result {
+        Ok(result) => result,
+        Err(error) => Err(Error::new(
+            ErrorKind::TaskFailure,
+            format!("pipeline task failed: {}", error).as_str(),
+        )),
+    }
+}
@@ -0,0 +1,42 @@
+use log::{Log, Metadata, Record};
+
+use crate::secret;
+
+/// Logger which removes every registered secret from the messages before handing them over.
+pub struct RedactingLogger<L: Log> {
+    inner: L,
+}
+
+impl<L: Log> RedactingLogger<L> {
+    pub fn new(inner: L) -> RedactingLogger<L> {
+        RedactingLogger { inner }
+    }
+}
+
+impl<L: Log> Log for RedactingLogger<L> {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let message = secret::redact(record.args().to_string().as_str());
+
+        self.inner.log(
+            &Record::builder()
+                .args(format_args!("{}", message))
+                .metadata(record.metadata().clone())
+                .module_path(record.module_path())
+                .file(record.file())
+                .line(record.line())
+                .build(),
+        );
+    }
+
+    fn flush(&self) {
+        self.inner.flush()
+    }
+}
@@ -0,0 +1,185 @@
+use std::time::{Duration, Instant};
+
+use rand::Rng;
+
+use crate::{
+    config::{degraded_mode_config::DegradedModeConfig, retry_policy_config::RetryPolicyConfig},
+    error::{Error, ErrorKind},
+    status::status_reporter::{Stage, StatusReporter},
+};
+
+/// Computes the delays between the attempts of a failing operation.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    config: RetryPolicyConfig,
+}
+
+impl RetryPolicy {
+    pub fn new(config: RetryPolicyConfig) -> RetryPolicy {
+        RetryPolicy { config }
+    }
+
+    /// Starts tracking the retries of an operation which has just failed for the first time.
+    pub fn start(&self) -> Retry {
+        Retry {
+            policy: self.clone(),
+            retries: 0,
+            started: Instant::now(),
+        }
+    }
+
+    /// Delay before the retry number `retry`, starting at 0, jitter included.
+    fn delay(&self, retry: u32) -> Duration {
+        let initial = self.config.initial_interval_seconds as f64;
+        let max = self.config.max_interval_seconds as f64;
+
+        let exponent = i32::try_from(retry).unwrap_or(i32::MAX);
+        let delay = (initial * self.config.multiplier.powi(exponent)).min(max);
+
+        let jitter = self.config.jitter.clamp(0.0, 1.0);
+        let delay = if jitter > 0.0 {
+            delay * (1.0 + rand::thread_rng().gen_range(-jitter..=jitter))
+        } else {
+            delay
+        };
+
+        Duration::from_secs_f64(delay.clamp(0.0, max.max(initial)))
+    }
+}
+
+/// Retries of a single failing operation.
+pub struct Retry {
+    policy: RetryPolicy,
+    retries: u32,
+    started: Instant,
+}
+
+impl Retry {
+    /// Delay to wait before retrying, or `None` once the attempts or the deadline are exhausted.
+    pub fn next_delay(&mut self) -> Option<Duration> {
+        if self.retries >= self.policy.config.max_retries {
+            return None;
+        }
+
+        let delay = self.policy.delay(self.retries);
+
+        if let Some(deadline_seconds) = self.policy.config.deadline_seconds {
+            if self.started.elapsed() + delay > Duration::from_secs(deadline_seconds) {
+                return None;
+            }
+        }
+
+        self.retries += 1;
+
+        Some(delay)
+    }
+
+    /// Retries made so far.
+    pub fn retries(&self) -> u32 {
+        self.retries
+    }
+}
+
+/// Reports `stage` as degraded once its retries are exhausted, returning the delay before the
+/// next attempt, or an error if the degraded mode is configured to exit.
+pub fn degrade(
+    config: &DegradedModeConfig,
+    status_reporter: &StatusReporter,
+    stage: Stage,
+    message: &str,
+) -> Result<Duration, Error> {
+    status_reporter.set_degraded(stage, message);
+
+    if config.exit_on_exhausted_retries {
+        return Err(Error::new(
+            ErrorKind::RetriesExhausted,
+            format!("retries exhausted: {}", message).as_str(),
+        ));
+    }
+
+    log::error!(
+        "retries exhausted, retrying every {}s in degraded mode: {}",
+        config.retry_interval_seconds,
+        message
+    );
+
+    Ok(Duration::from_secs(config.retry_interval_seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(max_retries: u32, jitter: f64, deadline_seconds: Option<u64>) -> RetryPolicy {
+        RetryPolicy::new(RetryPolicyConfig {
+            max_retries,
+            initial_interval_seconds: 2,
+            max_interval_seconds: 10,
+            multiplier: 2.0,
+            jitter,
+            deadline_seconds,
+            is_set: true,
+        })
+    }
+
+    #[test]
+    fn grows_delay_up_to_max_interval() {
+        let policy = policy(10, 0.0, None);
+
+        let delays: Vec<u64> = (0..5).map(|retry| policy.delay(retry).as_secs()).collect();
+
+        assert_eq!(delays, vec![2, 4, 8, 10, 10]);
+        assert_eq!(policy.delay(u32::MAX), Duration::from_secs(10));
+    }
+
+    #[test]
+    fn keeps_jittered_delay_within_bounds() {
+        let policy = policy(10, 0.5, None);
+
+        for _ in 0..100 {
+            let delay = policy.delay(1).as_secs_f64();
+            assert!(
+                (2.0..=6.0).contains(&delay),
+                "delay {} out of bounds",
+                delay
+            );
+
+            // Jitter never exceeds the max interval.
+            assert!(policy.delay(4).as_secs_f64() <= 10.0);
+        }
+    }
+
+    #[test]
+    fn stops_after_max_retries() {
+        let mut retry = policy(3, 0.0, None).start();
+
+        let delays: Vec<Option<Duration>> = (0..4).map(|_| retry.next_delay()).collect();
+
+        assert_eq!(
+            delays,
+            vec![
+                Some(Duration::from_secs(2)),
+                Some(Duration::from_secs(4)),
+                Some(Duration::from_secs(8)),
+                None
+            ]
+        );
+        assert_eq!(retry.retries(), 3);
+    }
+
+    #[test]
+    fn never_retries_without_retries() {
+        assert_eq!(policy(0, 0.0, None).start().next_delay(), None);
+    }
+
+    #[test]
+    fn stops_before_deadline() {
+        let mut retry = policy(10, 0.0, Some(7)).start();
+
+        assert_eq!(retry.next_delay(), Some(Duration::from_secs(2)));
+        assert_eq!(retry.next_delay(), Some(Duration::from_secs(4)));
+        // Waiting 8 seconds would go past the deadline.
+        assert_eq!(retry.next_delay(), None);
+        assert_eq!(retry.retries(), 2);
+    }
+}
@@ -0,0 +1,3 @@
+pub mod provider_status;
+pub mod status_reporter;
+pub mod status_server;
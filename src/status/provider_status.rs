@@ -0,0 +1,72 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Health {
+    /// The stage has not completed its first operation yet.
+    Starting,
+    Healthy,
+    /// The stage exhausted its retries and keeps retrying with a long backoff.
+    Degraded,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StageStatus {
+    pub health: Health,
+    pub message: Option<String>,
+    /// Seconds since the Unix epoch at which the health last changed.
+    pub since: u64,
+}
+
+impl StageStatus {
+    pub fn new(health: Health, message: Option<String>) -> StageStatus {
+        StageStatus {
+            health,
+            message,
+            since: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or(0),
+        }
+    }
+}
+
+/// Changes found during a freeze window, which are only downloaded once the window ends.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PendingUpdate {
+    /// Revision available at the origin of the source, if it can be told without downloading it.
+    pub revision: Option<String>,
+    /// Seconds since the Unix epoch at which the freeze window ends.
+    pub held_until: u64,
+}
+
+/// Health of every stage of the provider.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderStatus {
+    pub downloader: StageStatus,
+    pub output: StageStatus,
+    pub pending_update: Option<PendingUpdate>,
+}
+
+impl ProviderStatus {
+    pub fn new() -> ProviderStatus {
+        ProviderStatus {
+            downloader: StageStatus::new(Health::Starting, None),
+            output: StageStatus::new(Health::Starting, None),
+            pending_update: None,
+        }
+    }
+
+    /// Whether no stage is degraded.
+    pub fn is_healthy(&self) -> bool {
+        self.downloader.health != Health::Degraded && self.output.health != Health::Degraded
+    }
+}
+
+impl Default for ProviderStatus {
+    fn default() -> Self {
+        ProviderStatus::new()
+    }
+}
@@ -0,0 +1,80 @@
+use std::sync::Arc;
+
+use tokio::sync::watch::{self, Receiver, Sender};
+
+use super::provider_status::{Health, PendingUpdate, ProviderStatus, StageStatus};
+
+#[derive(Debug, Clone, Copy)]
+pub enum Stage {
+    Downloader,
+    Output,
+}
+
+/// Shared handle through which every stage reports its health.
+#[derive(Clone)]
+pub struct StatusReporter {
+    sender: Arc<Sender<ProviderStatus>>,
+}
+
+impl StatusReporter {
+    pub fn new() -> StatusReporter {
+        let (sender, _) = watch::channel(ProviderStatus::new());
+
+        StatusReporter {
+            sender: Arc::new(sender),
+        }
+    }
+
+    pub fn subscribe(&self) -> Receiver<ProviderStatus> {
+        self.sender.subscribe()
+    }
+
+    pub fn status(&self) -> ProviderStatus {
+        self.sender.borrow().clone()
+    }
+
+    pub fn set_healthy(&self, stage: Stage) {
+        self.set(stage, Health::Healthy, None);
+    }
+
+    pub fn set_degraded(&self, stage: Stage, message: &str) {
+        self.set(stage, Health::Degraded, Some(message.to_string()));
+    }
+
+    pub fn set_pending_update(&self, pending_update: Option<PendingUpdate>) {
+        self.sender.send_if_modified(|status| {
+            if status.pending_update == pending_update {
+                return false;
+            }
+
+            status.pending_update = pending_update;
+            true
+        });
+    }
+
+    fn set(&self, stage: Stage, health: Health, message: Option<String>) {
+        self.sender.send_if_modified(|status| {
+            let stage_status = match stage {
+                Stage::Downloader => &mut status.downloader,
+                Stage::Output => &mut status.output,
+            };
+
+            if stage_status.health == health && stage_status.message == message {
+                return false;
+            }
+
+            if stage_status.health != health {
+                log::info!("{:?} health changed to {:?}", stage, health);
+            }
+
+            *stage_status = StageStatus::new(health, message);
+            true
+        });
+    }
+}
+
+impl Default for StatusReporter {
+    fn default() -> Self {
+        StatusReporter::new()
+    }
+}
@@ -0,0 +1,126 @@
+use std::{io::Error, sync::Arc, time::Duration};
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    sync::Semaphore,
+};
+
+use crate::secret;
+
+use super::status_reporter::StatusReporter;
+
+const MAX_REQUEST_SIZE: usize = 8192;
+const MAX_CONNECTIONS: usize = 16;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+const DEFAULT_HOST: &str = "127.0.0.1";
+
+/// Minimal HTTP endpoint exposing the provider's status:
+///
+/// - `GET /health`: `200` unless a stage is degraded, then `503`.
+/// - `GET /status`: health of every stage as JSON.
+///
+/// At most `MAX_CONNECTIONS` requests are answered at once, each within `REQUEST_TIMEOUT`.
+pub struct StatusServer {
+    listen_address: String,
+    status_reporter: StatusReporter,
+}
+
+impl StatusServer {
+    /// A `listen_address` made of a port alone listens on the loopback interface only.
+    pub fn new(listen_address: &str, status_reporter: StatusReporter) -> StatusServer {
+        let listen_address = match listen_address.parse::<u16>() {
+            Ok(port) => format!("{}:{}", DEFAULT_HOST, port),
+            Err(_) => listen_address.to_string(),
+        };
+
+        StatusServer {
+            listen_address,
+            status_reporter,
+        }
+    }
+
+    pub async fn run(&self) -> Result<(), Error> {
+        let listener = TcpListener::bind(self.listen_address.as_str()).await?;
+        log::info!("status server listening on {}", self.listen_address);
+
+        let connections = Arc::new(Semaphore::new(MAX_CONNECTIONS));
+
+        loop {
+            // Waiting for a free slot before accepting leaves further connections in the backlog.
+            let permit = match connections.clone().acquire_owned().await {
+                Ok(permit) => permit,
+                Err(error) => return Err(Error::other(error)),
+            };
+            let (stream, _) = listener.accept().await?;
+            let status_reporter = self.status_reporter.clone();
+
+            tokio::spawn(async move {
+                match tokio::time::timeout(REQUEST_TIMEOUT, respond(stream, status_reporter)).await
+                {
+                    Ok(Ok(())) => (),
+                    Ok(Err(error)) => log::debug!("failed to answer status request: {}", error),
+                    Err(_) => log::debug!(
+                        "status request not answered within {} seconds",
+                        REQUEST_TIMEOUT.as_secs()
+                    ),
+                }
+
+                drop(permit);
+            });
+        }
+    }
+}
+
+async fn respond(mut stream: TcpStream, status_reporter: StatusReporter) -> Result<(), Error> {
+    let mut buffer = vec![0; MAX_REQUEST_SIZE];
+    let mut read = 0;
+
+    // Only the request line is needed, which may arrive split across several reads.
+    while read < buffer.len() && !buffer[..read].contains(&b'\n') {
+        match stream.read(&mut buffer[read..]).await? {
+            0 => break,
+            count => read += count,
+        }
+    }
+
+    let request = String::from_utf8_lossy(&buffer[..read]);
+
+    let path = request
+        .lines()
+        .next()
+        .and_then(|request_line| request_line.split_whitespace().nth(1))
+        .unwrap_or("/");
+
+    let status = status_reporter.status();
+    let (code, body) = match path {
+        "/health" => {
+            if status.is_healthy() {
+                ("200 OK", String::from("{\"healthy\":true}"))
+            } else {
+                (
+                    "503 Service Unavailable",
+                    String::from("{\"healthy\":false}"),
+                )
+            }
+        }
+        "/status" => match serde_json::to_string(&status) {
+            Ok(body) => ("200 OK", secret::redact(body.as_str())),
+            Err(error) => (
+                "500 Internal Server Error",
+                format!("{{\"error\":\"{}\"}}", error),
+            ),
+        },
+        _ => ("404 Not Found", String::from("{\"error\":\"not found\"}")),
+    };
+
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        code,
+        body.len(),
+        body
+    );
+
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
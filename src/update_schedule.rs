@@ -0,0 +1,398 @@
+use std::{str::FromStr, time::Duration};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use cron::Schedule;
+
+use crate::config::{
+    definition_downloader_config::DefinitionDownloaderConfig,
+    freeze_window_config::FreezeWindowConfig,
+};
+
+/// Weekday names by their number within a crontab expression, which starts the week on Sunday.
+const WEEKDAY_NAMES: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+
+/// Freeze windows followed in a row before giving up looking for the end of overlapping ones.
+const MAX_CHAINED_FREEZE_WINDOWS: usize = 1000;
+
+/// Times the source is polled at and periods during which updated definitions are held back.
+#[derive(Default)]
+pub struct UpdateSchedule {
+    schedules: Vec<Schedule>,
+    freeze_windows: Vec<FreezeWindow>,
+}
+
+enum FreezeWindow {
+    Period {
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    },
+    Recurring {
+        start: Box<Schedule>,
+        duration: TimeDelta,
+    },
+}
+
+impl UpdateSchedule {
+    pub fn new(config: &DefinitionDownloaderConfig) -> Result<UpdateSchedule, String> {
+        let mut schedules: Vec<Schedule> = Vec::new();
+        for expression in &config.update_schedule {
+            schedules.push(parse_cron(expression)?);
+        }
+
+        let mut freeze_windows: Vec<FreezeWindow> = Vec::new();
+        for freeze_window in &config.freeze_windows {
+            freeze_windows.push(match freeze_window {
+                FreezeWindowConfig::Period { start, end } => FreezeWindow::Period {
+                    start: parse_timestamp(start)?,
+                    end: parse_timestamp(end)?,
+                },
+                FreezeWindowConfig::Recurring {
+                    start,
+                    duration_minutes,
+                } => FreezeWindow::Recurring {
+                    start: Box::new(parse_cron(start)?),
+                    duration: i64::try_from(*duration_minutes)
+                        .ok()
+                        .and_then(TimeDelta::try_minutes)
+                        .unwrap_or(TimeDelta::MAX),
+                },
+            });
+        }
+
+        Ok(UpdateSchedule {
+            schedules,
+            freeze_windows,
+        })
+    }
+
+    /// Delay from `now` until the next scheduled poll, or `None` if polls are not scheduled.
+    pub fn until_next_update(&self, now: DateTime<Utc>) -> Option<Duration> {
+        let next_update = self
+            .schedules
+            .iter()
+            .filter_map(|schedule| schedule.after(&now).next())
+            .min()?;
+
+        Some((next_update - now).to_std().unwrap_or_default())
+    }
+
+    /// End of the freeze `now` falls within, if any, following windows which overlap it.
+    pub fn freeze_end(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let mut end = self.window_end(now)?;
+
+        for _ in 0..MAX_CHAINED_FREEZE_WINDOWS {
+            match self.window_end(end) {
+                Some(next_end) if next_end > end => end = next_end,
+                _ => break,
+            }
+        }
+
+        Some(end)
+    }
+
+    /// Latest end of the windows `time` falls within.
+    fn window_end(&self, time: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.freeze_windows
+            .iter()
+            .filter_map(|freeze_window| freeze_window.end_if_within(time))
+            .max()
+    }
+}
+
+impl FreezeWindow {
+    fn end_if_within(&self, time: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let (start, end) = match self {
+            FreezeWindow::Period { start, end } => (*start, *end),
+            FreezeWindow::Recurring { start, duration } => {
+                // Latest start at or before `time`, cron expressions matching whole seconds.
+                let start = start.after(&(time + TimeDelta::seconds(1))).next_back()?;
+                (start, start.checked_add_signed(*duration)?)
+            }
+        };
+
+        if start <= time && time < end {
+            Some(end)
+        } else {
+            None
+        }
+    }
+}
+
+/// Parses a cron expression with five fields, `minute hour day month weekday`, or with the seconds
+/// and optionally the years as well.
+///
+/// Weekdays may be named, such as `Mon-Fri`. Their numbers go from 0 or 7 for Sunday to 6 for
+/// Saturday with five fields, as in crontab, but from 1 for Sunday to 7 for Saturday otherwise.
+pub fn parse_cron(expression: &str) -> Result<Schedule, String> {
+    let fields: Vec<&str> = expression.split_whitespace().collect();
+
+    let full_expression = match fields.as_slice() {
+        [minute, hour, day, month, weekday] => match name_weekdays(weekday) {
+            Ok(weekday) => format!("0 {} {} {} {} {}", minute, hour, day, month, weekday),
+            Err(error) => {
+                return Err(format!(
+                    "invalid cron expression '{}': {}",
+                    expression, error
+                ))
+            }
+        },
+        _ => expression.to_string(),
+    };
+
+    match Schedule::from_str(full_expression.as_str()) {
+        Ok(schedule) => Ok(schedule),
+        Err(error) => Err(format!(
+            "invalid cron expression '{}': {}",
+            expression, error
+        )),
+    }
+}
+
+/// Names the weekdays a crontab weekday field lists by number, such as `1-5` or `*/2`, as the cron
+/// crate numbers them differently. Fields which already name them are kept as they are.
+fn name_weekdays(field: &str) -> Result<String, String> {
+    let is_numeric = field
+        .chars()
+        .all(|character| character.is_ascii_digit() || ",-/*".contains(character));
+
+    if field == "*" || !is_numeric {
+        return Ok(field.to_string());
+    }
+
+    let mut weekdays = [false; 7];
+
+    for item in field.split(',') {
+        let (range, step) = match item.split_once('/') {
+            Some((range, step)) => (range, parse_number(step, 1, 7)?),
+            None => (item, 1),
+        };
+
+        let (first, last) = match range.split_once('-') {
+            _ if range == "*" => (0, 6),
+            Some((first, last)) => (parse_number(first, 0, 7)?, parse_number(last, 0, 7)?),
+            // A single weekday with a step, such as `1/2`, goes on until the end of the week.
+            None if item.contains('/') => (parse_number(range, 0, 7)?, 6),
+            None => {
+                let weekday = parse_number(range, 0, 7)?;
+                (weekday, weekday)
+            }
+        };
+
+        if first > last {
+            return Err(format!("invalid weekday range '{}'", range));
+        }
+
+        for weekday in (first..=last).step_by(step as usize) {
+            weekdays[weekday as usize % WEEKDAY_NAMES.len()] = true;
+        }
+    }
+
+    Ok(WEEKDAY_NAMES
+        .iter()
+        .zip(weekdays)
+        .filter(|(_, listed)| *listed)
+        .map(|(name, _)| *name)
+        .collect::<Vec<&str>>()
+        .join(","))
+}
+
+fn parse_number(number: &str, min: u32, max: u32) -> Result<u32, String> {
+    match number.parse::<u32>() {
+        Ok(number) if (min..=max).contains(&number) => Ok(number),
+        _ => Err(format!(
+            "invalid weekday number '{}', expected {} to {}",
+            number, min, max
+        )),
+    }
+}
+
+/// Parses an RFC 3339 timestamp, such as `2024-12-23T00:00:00Z`.
+pub fn parse_timestamp(timestamp: &str) -> Result<DateTime<Utc>, String> {
+    match DateTime::parse_from_rfc3339(timestamp) {
+        Ok(timestamp) => Ok(timestamp.with_timezone(&Utc)),
+        Err(error) => Err(format!("invalid timestamp '{}': {}", timestamp, error)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(timestamp: &str) -> DateTime<Utc> {
+        parse_timestamp(timestamp).unwrap()
+    }
+
+    fn next(expression: &str, after: &str) -> DateTime<Utc> {
+        parse_cron(expression)
+            .unwrap()
+            .after(&time(after))
+            .next()
+            .unwrap()
+    }
+
+    fn period(start: &str, end: &str) -> FreezeWindow {
+        FreezeWindow::Period {
+            start: time(start),
+            end: time(end),
+        }
+    }
+
+    fn recurring(start: &str, duration_minutes: i64) -> FreezeWindow {
+        FreezeWindow::Recurring {
+            start: Box::new(parse_cron(start).unwrap()),
+            duration: TimeDelta::minutes(duration_minutes),
+        }
+    }
+
+    fn freezing(freeze_windows: Vec<FreezeWindow>) -> UpdateSchedule {
+        UpdateSchedule {
+            schedules: Vec::new(),
+            freeze_windows,
+        }
+    }
+
+    // 2024-06-02 is a Sunday.
+
+    #[test]
+    fn numbers_weekdays_from_sunday_with_five_fields() {
+        assert_eq!(
+            next("0 9 * * 1-5", "2024-06-01T12:00:00Z"),
+            time("2024-06-03T09:00:00Z")
+        );
+        assert_eq!(
+            next("0 9 * * 0", "2024-06-01T12:00:00Z"),
+            time("2024-06-02T09:00:00Z")
+        );
+        assert_eq!(
+            next("0 9 * * 7", "2024-06-01T12:00:00Z"),
+            time("2024-06-02T09:00:00Z")
+        );
+        assert_eq!(
+            next("0 9 * * 6,0", "2024-06-03T12:00:00Z"),
+            time("2024-06-08T09:00:00Z")
+        );
+        assert_eq!(
+            next("0 9 * * 5-7", "2024-06-08T12:00:00Z"),
+            time("2024-06-09T09:00:00Z")
+        );
+        assert_eq!(
+            next("0 9 * * 1/2", "2024-06-03T12:00:00Z"),
+            time("2024-06-05T09:00:00Z")
+        );
+    }
+
+    #[test]
+    fn keeps_named_weekdays_and_longer_expressions() {
+        assert_eq!(
+            next("30 0 9 * * Mon-Fri", "2024-06-01T12:00:00Z"),
+            time("2024-06-03T09:00:30Z")
+        );
+        assert_eq!(
+            next("0 9 * * Sat", "2024-06-03T12:00:00Z"),
+            time("2024-06-08T09:00:00Z")
+        );
+        // The cron crate numbers weekdays from 1 for Sunday.
+        assert_eq!(
+            next("0 0 9 * * 1", "2024-06-01T12:00:00Z"),
+            time("2024-06-02T09:00:00Z")
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_weekdays() {
+        assert!(parse_cron("0 9 * * 8").is_err());
+        assert!(parse_cron("0 9 * * 5-1").is_err());
+        assert!(parse_cron("0 9 * * */0").is_err());
+        assert!(parse_cron("0 9 * *").is_err());
+    }
+
+    #[test]
+    fn ends_freeze_of_period() {
+        let update_schedule =
+            freezing(vec![period("2024-06-01T00:00:00Z", "2024-06-02T00:00:00Z")]);
+
+        assert_eq!(
+            update_schedule.freeze_end(time("2024-06-01T00:00:00Z")),
+            Some(time("2024-06-02T00:00:00Z"))
+        );
+        assert_eq!(
+            update_schedule.freeze_end(time("2024-05-31T23:59:59Z")),
+            None
+        );
+        assert_eq!(
+            update_schedule.freeze_end(time("2024-06-02T00:00:00Z")),
+            None
+        );
+    }
+
+    #[test]
+    fn follows_overlapping_and_chained_windows() {
+        let update_schedule = freezing(vec![
+            period("2024-06-01T00:00:00Z", "2024-06-02T00:00:00Z"),
+            period("2024-06-01T12:00:00Z", "2024-06-03T00:00:00Z"),
+            period("2024-06-03T00:00:00Z", "2024-06-04T00:00:00Z"),
+            period("2024-06-05T00:00:00Z", "2024-06-06T00:00:00Z"),
+        ]);
+
+        assert_eq!(
+            update_schedule.freeze_end(time("2024-06-01T06:00:00Z")),
+            Some(time("2024-06-04T00:00:00Z"))
+        );
+        assert_eq!(
+            update_schedule.freeze_end(time("2024-06-05T06:00:00Z")),
+            Some(time("2024-06-06T00:00:00Z"))
+        );
+    }
+
+    #[test]
+    fn chains_recurring_windows_with_periods() {
+        // Every day from 22:00 to 02:00, and the whole 3rd of June.
+        let update_schedule = freezing(vec![
+            recurring("0 22 * * *", 240),
+            period("2024-06-03T00:00:00Z", "2024-06-04T00:00:00Z"),
+        ]);
+
+        assert_eq!(
+            update_schedule.freeze_end(time("2024-06-02T23:00:00Z")),
+            Some(time("2024-06-04T02:00:00Z"))
+        );
+    }
+
+    #[test]
+    fn finds_latest_start_of_recurring_window() {
+        let update_schedule = freezing(vec![recurring("0 22 * * *", 240)]);
+
+        assert_eq!(
+            update_schedule.freeze_end(time("2024-06-01T22:00:00Z")),
+            Some(time("2024-06-02T02:00:00Z"))
+        );
+        // Started the day before.
+        assert_eq!(
+            update_schedule.freeze_end(time("2024-06-02T01:59:59Z")),
+            Some(time("2024-06-02T02:00:00Z"))
+        );
+        assert_eq!(
+            update_schedule.freeze_end(time("2024-06-02T02:00:00Z")),
+            None
+        );
+        assert_eq!(
+            update_schedule.freeze_end(time("2024-06-01T21:59:59Z")),
+            None
+        );
+    }
+
+    #[test]
+    fn freezes_recurring_window_on_numbered_weekdays() {
+        // Fridays from 18:00 until Monday at 06:00.
+        let update_schedule = freezing(vec![recurring("0 18 * * 5", 3600)]);
+
+        assert_eq!(
+            update_schedule.freeze_end(time("2024-06-02T12:00:00Z")),
+            Some(time("2024-06-03T06:00:00Z"))
+        );
+        assert_eq!(
+            update_schedule.freeze_end(time("2024-06-03T12:00:00Z")),
+            None
+        );
+    }
+}